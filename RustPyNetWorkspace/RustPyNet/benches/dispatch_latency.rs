@@ -0,0 +1,59 @@
+//! Measures the round trip of a trivial Python task through a `PythonPool`: the time from
+//! `enqueue` on the caller thread until the result is received back.
+//!
+//! Run with `cargo bench --bench dispatch_latency`.
+
+use std::time::{Duration, Instant};
+
+use pyo3::Python;
+use RustPyNet::python_pool::pool::{
+    MyResult, PythonPool, PythonTask, PythonTaskError, PythonTaskQueue, PythonTaskResult,
+    ShutdownMode,
+};
+
+const WARMUP_ROUNDS: usize = 200;
+const MEASURED_ROUNDS: usize = 5_000;
+
+/// Evaluates `1 + 2` in Python, which takes a couple of microseconds.
+struct AddTask;
+
+impl PythonTask for AddTask {
+    fn execute(&self, py: Python) -> MyResult<PythonTaskResult> {
+        py.eval("1 + 2", None, None)
+            .and_then(|value| value.extract::<i32>())
+            .map(PythonTaskResult::Int)
+            .map_err(PythonTaskError::from)
+    }
+}
+
+fn round_trip(queue: &PythonTaskQueue) -> Duration {
+    let started = Instant::now();
+    let rx = queue.enqueue(Box::new(AddTask));
+    PythonTaskQueue::wait_for_result(rx).expect("The benchmark task failed");
+    started.elapsed()
+}
+
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    sorted[(sorted.len() - 1) * percent / 100]
+}
+
+fn main() {
+    let queue = PythonTaskQueue::new();
+    let pool = PythonPool::start_on(&queue).expect("Failed to start the Python pool");
+
+    for _ in 0..WARMUP_ROUNDS {
+        round_trip(&queue);
+    }
+
+    let mut samples: Vec<Duration> = (0..MEASURED_ROUNDS).map(|_| round_trip(&queue)).collect();
+    samples.sort();
+
+    let total: Duration = samples.iter().sum();
+    println!("dispatch latency over {} round trips:", MEASURED_ROUNDS);
+    println!("  mean   {:?}", total / MEASURED_ROUNDS as u32);
+    println!("  median {:?}", percentile(&samples, 50));
+    println!("  p99    {:?}", percentile(&samples, 99));
+    println!("  max    {:?}", samples[samples.len() - 1]);
+
+    pool.shutdown(ShutdownMode::Drain);
+}
@@ -0,0 +1,233 @@
+//! Measures how many tasks per second a `PythonPool` gets through when several threads submit
+//! small tasks at once, which is dominated by the cost of enqueueing rather than by Python.
+//!
+//! Every run is also made against a baseline: a bare `Mutex<VecDeque>` that producers and the
+//! worker all lock, and a condition variable the worker waits on, woken up by every enqueue. It
+//! locks the same way as `PythonTaskQueue`, without any of its features, and shows how much of
+//! the cost of the pool comes from the queue itself.
+//!
+//! Two rates are reported for each. The submit rate covers the time until every producer has
+//! enqueued its share, which is what the queue design changes. The end-to-end rate covers the
+//! time until every result was received. The baseline worker skips the bookkeeping the pool does
+//! around every task (handles, cancellation, spans, the watchdog, catching panics), so its
+//! end-to-end rate is not comparable to the pool's, only an upper bound of what the queue alone
+//! allows.
+//!
+//! Run with `cargo bench --bench submit_throughput`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Barrier, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pyo3::Python;
+use RustPyNet::python_pool::pool::{
+    MyResult, PythonPool, PythonTask, PythonTaskQueue, PythonTaskResult, ShutdownMode,
+};
+
+const PRODUCERS: [usize; 5] = [1, 2, 4, 8, 16];
+const TASKS: usize = 200_000;
+const ROUNDS: usize = 3;
+
+/// Returns right away, so that the benchmark measures the queue and not the task.
+struct NoopTask;
+
+impl PythonTask for NoopTask {
+    fn execute(&self, _py: Python) -> MyResult<PythonTaskResult> {
+        Ok(PythonTaskResult::None)
+    }
+}
+
+/// Where the benchmark submits its tasks.
+trait Submitter: Clone + Send + 'static {
+    /// A handle to the result of a submitted task.
+    type Handle;
+
+    fn submit(&self, task: NoopTask) -> Self::Handle;
+
+    fn wait(handle: Self::Handle);
+}
+
+impl Submitter for PythonTaskQueue {
+    type Handle = RustPyNet::python_pool::task_handle::TaskHandle<PythonTaskResult>;
+
+    fn submit(&self, task: NoopTask) -> Self::Handle {
+        self.enqueue(Box::new(task))
+    }
+
+    fn wait(handle: Self::Handle) {
+        handle.wait().expect("The benchmark task failed");
+    }
+}
+
+/// The result of a task submitted to the baseline queue.
+#[derive(Default)]
+struct Slot {
+    value: Mutex<Option<MyResult<PythonTaskResult>>>,
+    ready: Condvar,
+}
+
+#[derive(Default)]
+struct BaselineShared {
+    tasks: Mutex<BaselineState>,
+    available: Condvar,
+}
+
+#[derive(Default)]
+struct BaselineState {
+    queue: VecDeque<(Box<dyn PythonTask + Send>, Arc<Slot>)>,
+    stopped: bool,
+}
+
+/// A mutex-guarded queue without priorities, capacity, cancellation or shutdown.
+#[derive(Clone, Default)]
+struct BaselineQueue {
+    shared: Arc<BaselineShared>,
+}
+
+impl BaselineQueue {
+    /// Starts the worker processing the queue, which holds the GIL while tasks are ready.
+    fn start(&self) -> thread::JoinHandle<()> {
+        let shared = self.shared.clone();
+        thread::spawn(move || loop {
+            let mut tasks = shared.tasks.lock().unwrap();
+            while tasks.queue.is_empty() && !tasks.stopped {
+                tasks = shared.available.wait(tasks).unwrap();
+            }
+            if tasks.queue.is_empty() {
+                return;
+            }
+            drop(tasks);
+
+            let gil_guard = Python::acquire_gil();
+            let py = gil_guard.python();
+            while let Some((task, slot)) = shared.tasks.lock().unwrap().queue.pop_front() {
+                let result = task.execute(py);
+                *slot.value.lock().unwrap() = Some(result);
+                slot.ready.notify_all();
+            }
+        })
+    }
+
+    fn stop(&self, worker: thread::JoinHandle<()>) {
+        self.shared.tasks.lock().unwrap().stopped = true;
+        self.shared.available.notify_all();
+        worker.join().unwrap();
+    }
+}
+
+impl Submitter for BaselineQueue {
+    type Handle = Arc<Slot>;
+
+    fn submit(&self, task: NoopTask) -> Self::Handle {
+        let slot = Arc::new(Slot::default());
+        self.shared
+            .tasks
+            .lock()
+            .unwrap()
+            .queue
+            .push_back((Box::new(task), slot.clone()));
+        self.shared.available.notify_one();
+        slot
+    }
+
+    fn wait(handle: Self::Handle) {
+        let mut value = handle.value.lock().unwrap();
+        while value.is_none() {
+            value = handle.ready.wait(value).unwrap();
+        }
+        value.take().unwrap().expect("The benchmark task failed");
+    }
+}
+
+/// How long a run took.
+#[derive(Clone, Copy)]
+struct Timings {
+    /// Until the last producer finished enqueueing.
+    submitted: Duration,
+    /// Until the last result was received.
+    completed: Duration,
+}
+
+/// Submits `TASKS` tasks split between `producers` threads, and waits until all of them were
+/// executed.
+fn run<S: Submitter>(submitter: &S, producers: usize) -> Timings {
+    let start = Arc::new(Barrier::new(producers + 1));
+    let threads: Vec<_> = (0..producers)
+        .map(|_| {
+            let submitter = submitter.clone();
+            let start = start.clone();
+            thread::spawn(move || {
+                start.wait();
+                let handles: Vec<_> = (0..TASKS / producers)
+                    .map(|_| submitter.submit(NoopTask))
+                    .collect();
+                let submitted = Instant::now();
+                for handle in handles {
+                    S::wait(handle);
+                }
+                submitted
+            })
+        })
+        .collect();
+
+    start.wait();
+    let started = Instant::now();
+    let submitted = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .max()
+        .unwrap();
+    Timings {
+        submitted: submitted - started,
+        completed: started.elapsed(),
+    }
+}
+
+/// The best submit and end-to-end rates out of `ROUNDS` runs, in tasks per second.
+fn throughput<S: Submitter>(submitter: &S, producers: usize) -> (f64, f64) {
+    let runs: Vec<_> = (0..ROUNDS).map(|_| run(submitter, producers)).collect();
+    let tasks = (TASKS / producers * producers) as f64;
+    let best = |duration: fn(&Timings) -> Duration| {
+        tasks / runs.iter().map(duration).min().unwrap().as_secs_f64()
+    };
+    (
+        best(|timings| timings.submitted),
+        best(|timings| timings.completed),
+    )
+}
+
+fn main() {
+    let queue = PythonTaskQueue::new();
+    let pool = PythonPool::start_on(&queue).expect("Failed to start the Python pool");
+    let baseline = BaselineQueue::default();
+    let baseline_worker = baseline.start();
+
+    run(&queue, 1);
+    run(&baseline, 1);
+
+    println!(
+        "{} tasks per run, best of {} runs, in tasks/s:",
+        TASKS, ROUNDS
+    );
+    println!(
+        "  {:>9}  {:>12}  {:>12}  {:>7}  {:>12}  {:>12}",
+        "producers", "mutex submit", "pool submit", "ratio", "mutex e2e", "pool e2e"
+    );
+    for producers in PRODUCERS {
+        let (baseline_submit, baseline_e2e) = throughput(&baseline, producers);
+        let (pool_submit, pool_e2e) = throughput(&queue, producers);
+        println!(
+            "  {:>9}  {:>12.0}  {:>12.0}  {:>6.2}x  {:>12.0}  {:>12.0}",
+            producers,
+            baseline_submit,
+            pool_submit,
+            pool_submit / baseline_submit,
+            baseline_e2e,
+            pool_e2e
+        );
+    }
+
+    baseline.stop(baseline_worker);
+    pool.shutdown(ShutdownMode::Drain);
+}
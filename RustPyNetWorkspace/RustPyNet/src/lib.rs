@@ -1,14 +1,30 @@
+#![allow(non_snake_case)]
+
 use crate::python_pool::pool::PythonTaskQueue;
 use lazy_static::lazy_static;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::Mutex;
 pub mod python_pool;
 
+/// Items the code generated by `run_with_py` refers to, so that it doesn't depend on the imports
+/// of the file it expands in. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::python_pool::pool::{
+        MyResult, PythonTask, PythonTaskError, PythonTaskQueue, PythonTaskResult, TaskOptions,
+        TaskPriority,
+    };
+    pub use crate::python_pool::typed::{FnTask, TaskArgument, TaskReturn, TypedOutput};
+    pub use crate::CLIENT_PYTHON_PROCESS_QUEUE;
+    pub use pyo3::Python;
+}
+
 // RustPyNet/src/lib.rs or RustPyNet/src/mod.rs
 
 /// The `run_with_py` procedural macro facilitates the execution of a given function within a Python context.
 ///
 /// It dynamically creates a struct and its implementation based on the provided function. The function is then executed
-/// within a Python context, and its results are passed back through a channel.
+/// within a Python context, and its result is handed back to the caller by the pool.
 ///
 /// # Usage
 ///
@@ -53,15 +69,32 @@ pub mod python_pool;
 /// }
 /// ```
 ///
+/// ### Async use case exemple:
+///
+/// ```ignore
+/// #[run_with_py(async)]
+/// fn compute_sum_async(context: &PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+///     let sum: i32 = py.eval("1 + 2", None, None)?.extract()?;
+///     Ok(PythonTaskResult::Int(sum))
+/// }
+///
+/// // The caller awaits the result instead of blocking its thread, on any executor.
+/// let result = compute_sum_async(&context).await;
+/// ```
+///
 /// This macro will create the necessary infrastructure for the function to be run in a Python context.
 ///
 /// # Parameters
 ///
-/// - `dict`: A `HashMap` containing data that you wish to pass to the Python context.
+/// Any number of named parameters, whose types are `Send + 'static`; borrowed parameters are
+/// copied into the task with `ToOwned`.
+/// A `py: Python` parameter receives the GIL token of the worker and is not part of the generated
+/// functions.
 ///
 /// # Returns
 ///
-/// Returns whatever your function is intended to return, wrapped in the necessary channel and context management code.
+/// Returns whatever your function is intended to return, wrapped in the necessary queueing and context management code.
+/// See `python_pool::typed::TaskReturn` for the return types that are supported.
 ///
 /// # Errors
 ///
@@ -69,6 +102,10 @@ pub mod python_pool;
 pub use rustpynet_macros::run_with_py;
 
 lazy_static! {
-    pub static ref CLIENT_PYTHON_PROCESS_QUEUE: Mutex<PythonTaskQueue> =
-        Mutex::new(PythonTaskQueue::new());
+    /// The queue `run_with_py` functions are enqueued into, unless they name a pool.
+    pub static ref CLIENT_PYTHON_PROCESS_QUEUE: PythonTaskQueue = PythonTaskQueue::new();
+
+    /// The queues created by `PythonTaskQueue::named`, by name.
+    pub(crate) static ref NAMED_PYTHON_PROCESS_QUEUES: Mutex<HashMap<String, PythonTaskQueue>> =
+        Mutex::new(HashMap::new());
 }
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use serde::Deserialize;
+
+use crate::python_pool::pool::{
+    MyResult, OverflowPolicy, PythonPool, PythonTaskError, PythonTaskQueue, DEFAULT_AGING,
+    DEFAULT_RESTART_BACKOFF,
+};
+use crate::CLIENT_PYTHON_PROCESS_QUEUE;
+
+/// How the worker of a pool behaves, and how it prepares the interpreter before running tasks.
+#[derive(Clone, Debug)]
+pub(crate) struct WorkerOptions {
+    /// The most tasks executed in a row before the worker releases the GIL, `None` to keep it
+    /// while tasks are ready.
+    pub(crate) max_batch: Option<usize>,
+    /// Whether tasks still running when their deadline passes are interrupted.
+    pub(crate) interrupt_overdue: bool,
+    /// How long to wait before restarting a worker that exited unexpectedly, `None` to leave
+    /// the pool without a worker.
+    pub(crate) restart_backoff: Option<Duration>,
+    /// Directories appended to `sys.path`.
+    pub(crate) sys_path: Vec<PathBuf>,
+    /// Modules imported before the pool reports ready.
+    pub(crate) preload: Vec<String>,
+    /// Variables set in `os.environ`.
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        Self {
+            max_batch: None,
+            interrupt_overdue: true,
+            restart_backoff: Some(DEFAULT_RESTART_BACKOFF),
+            sys_path: Vec::new(),
+            preload: Vec::new(),
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+impl WorkerOptions {
+    /// Prepares the interpreter from the worker thread, before it starts executing tasks.
+    pub(crate) fn prepare(&self, py: Python) -> PyResult<()> {
+        if !self.env.is_empty() {
+            let environ = py.import("os")?.getattr("environ")?;
+            for (key, value) in &self.env {
+                environ.set_item(key, value)?;
+            }
+        }
+
+        if !self.sys_path.is_empty() {
+            let path: &PyList = py.import("sys")?.getattr("path")?.downcast()?;
+            for dir in &self.sys_path {
+                let dir = dir.to_string_lossy();
+                // Restarting a pool must not add the same directories again.
+                let present = path
+                    .iter()
+                    .any(|entry| entry.extract::<&str>().is_ok_and(|entry| entry == dir));
+                if !present {
+                    path.append(dir.as_ref())?;
+                }
+            }
+        }
+
+        for module in &self.preload {
+            py.import(module.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// The settings of a pool as read from a TOML file, every one of them being optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PoolConfig {
+    name: Option<String>,
+    capacity: Option<usize>,
+    overflow: Option<String>,
+    aging_ms: Option<u64>,
+    logging: Option<bool>,
+    max_batch: Option<usize>,
+    interrupt_overdue: Option<bool>,
+    restart_backoff_ms: Option<u64>,
+    #[serde(default)]
+    sys_path: Vec<PathBuf>,
+    #[serde(default)]
+    preload: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// Configures and starts a `PythonPool`.
+///
+/// Every setting has a default, so `PythonPoolBuilder::new().start()` is equivalent to
+/// `PythonPool::start()`. The settings can also be read from a TOML file with `from_toml_file`,
+/// or from `RUSTPYNET_*` environment variables with `from_env`.
+///
+/// # Usage
+///
+/// ```ignore
+/// let pool = PythonPoolBuilder::new()
+///     .name("analytics")
+///     .capacity(1_000)
+///     .overflow(OverflowPolicy::FailFast)
+///     .sys_path("scripts")
+///     .preload("numpy")
+///     .env("OMP_NUM_THREADS", "1")
+///     .start()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct PythonPoolBuilder {
+    name: Option<String>,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    aging: Option<Duration>,
+    logging: bool,
+    worker: WorkerOptions,
+}
+
+impl Default for PythonPoolBuilder {
+    fn default() -> Self {
+        Self {
+            name: None,
+            capacity: None,
+            overflow: OverflowPolicy::Block,
+            aging: Some(DEFAULT_AGING),
+            logging: true,
+            worker: WorkerOptions::default(),
+        }
+    }
+}
+
+impl PythonPoolBuilder {
+    /// Creates a builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the settings from the `RUSTPYNET_*` environment variables, see `env_overrides`.
+    pub fn from_env() -> MyResult<Self> {
+        Self::new().env_overrides()
+    }
+
+    /// Reads the settings from a TOML document.
+    ///
+    /// The keys are `name`, `capacity`, `overflow` (`"block"`, `"fail_fast"` or
+    /// `"drop_oldest"`), `aging_ms` (`0` disabling aging), `logging`, `max_batch`,
+    /// `interrupt_overdue`, `restart_backoff_ms` (`0` disabling restarts), `sys_path`,
+    /// `preload`, and an `[env]` table.
+    ///
+    /// ```toml
+    /// name = "analytics"
+    /// capacity = 1000
+    /// overflow = "fail_fast"
+    /// sys_path = ["scripts"]
+    /// preload = ["numpy"]
+    ///
+    /// [env]
+    /// OMP_NUM_THREADS = "1"
+    /// ```
+    pub fn from_toml_str(toml: &str) -> MyResult<Self> {
+        let config: PoolConfig = toml::from_str(toml).map_err(|err| {
+            PythonTaskError::OtherError(format!("Invalid pool configuration: {}", err))
+        })?;
+        Self::new().apply(config)
+    }
+
+    /// Reads the settings from a TOML file, see `from_toml_str`.
+    pub fn from_toml_file(path: impl AsRef<Path>) -> MyResult<Self> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path).map_err(|err| {
+            PythonTaskError::OtherError(format!(
+                "Failed to read the pool configuration {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        Self::from_toml_str(&toml)
+    }
+
+    /// Overrides the settings with the `RUSTPYNET_*` environment variables that are set.
+    ///
+    /// The variables are `RUSTPYNET_NAME`, `RUSTPYNET_CAPACITY`, `RUSTPYNET_OVERFLOW`,
+    /// `RUSTPYNET_AGING_MS`, `RUSTPYNET_LOGGING`, `RUSTPYNET_MAX_BATCH`,
+    /// `RUSTPYNET_INTERRUPT_OVERDUE`, `RUSTPYNET_RESTART_BACKOFF_MS`, `RUSTPYNET_SYS_PATH` (a
+    /// list of directories separated like `PATH`), `RUSTPYNET_PRELOAD` (a comma-separated list
+    /// of modules), and `RUSTPYNET_ENV` (comma-separated `KEY=VALUE` pairs, so values can't
+    /// contain commas). They take the same values as the keys of the TOML configuration.
+    pub fn env_overrides(self) -> MyResult<Self> {
+        self.env_overrides_with(|key| std::env::var_os(key))
+    }
+
+    /// Overrides the settings with the `RUSTPYNET_*` variables that `lookup` returns, instead of
+    /// reading them from the environment of the process, see `env_overrides`.
+    ///
+    /// This lets the variables come from another source, such as a `.env` file, and lets tests
+    /// avoid changing the environment of the process while other threads may read it.
+    pub fn env_overrides_with(self, lookup: impl Fn(&str) -> Option<OsString>) -> MyResult<Self> {
+        let config = PoolConfig {
+            name: env_var(&lookup, "RUSTPYNET_NAME")?,
+            capacity: env_var(&lookup, "RUSTPYNET_CAPACITY")?,
+            overflow: env_var(&lookup, "RUSTPYNET_OVERFLOW")?,
+            aging_ms: env_var(&lookup, "RUSTPYNET_AGING_MS")?,
+            logging: env_var(&lookup, "RUSTPYNET_LOGGING")?,
+            max_batch: env_var(&lookup, "RUSTPYNET_MAX_BATCH")?,
+            interrupt_overdue: env_var(&lookup, "RUSTPYNET_INTERRUPT_OVERDUE")?,
+            restart_backoff_ms: env_var(&lookup, "RUSTPYNET_RESTART_BACKOFF_MS")?,
+            sys_path: lookup("RUSTPYNET_SYS_PATH")
+                .map(|paths| std::env::split_paths(&paths).collect())
+                .unwrap_or_default(),
+            preload: env_var::<String>(&lookup, "RUSTPYNET_PRELOAD")?
+                .map(|modules| {
+                    modules
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|module| !module.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            env: env_var::<String>(&lookup, "RUSTPYNET_ENV")?
+                .map(|pairs| parse_env_pairs("RUSTPYNET_ENV", &pairs))
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        self.apply(config)
+    }
+
+    fn apply(mut self, config: PoolConfig) -> MyResult<Self> {
+        if let Some(name) = config.name {
+            self.name = Some(name);
+        }
+        if let Some(capacity) = config.capacity {
+            self.capacity = Some(capacity);
+        }
+        if let Some(overflow) = config.overflow {
+            self.overflow = overflow.parse()?;
+        }
+        if let Some(aging_ms) = config.aging_ms {
+            self.aging = Some(Duration::from_millis(aging_ms)).filter(|aging| !aging.is_zero());
+        }
+        if let Some(logging) = config.logging {
+            self.logging = logging;
+        }
+        if let Some(max_batch) = config.max_batch {
+            self.worker.max_batch = Some(max_batch);
+        }
+        if let Some(interrupt_overdue) = config.interrupt_overdue {
+            self.worker.interrupt_overdue = interrupt_overdue;
+        }
+        if let Some(restart_backoff_ms) = config.restart_backoff_ms {
+            self.worker.restart_backoff = Some(Duration::from_millis(restart_backoff_ms))
+                .filter(|backoff| !backoff.is_zero());
+        }
+        self.worker.sys_path.extend(config.sys_path);
+        self.worker.preload.extend(config.preload);
+        self.worker.env.extend(config.env);
+        Ok(self)
+    }
+
+    /// Processes the named queue, see `PythonTaskQueue::named`, instead of the global one.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Limits the queue to `capacity` tasks. Queues are unbounded by default.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Sets what happens when the queue is full, `OverflowPolicy::Block` by default.
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets how long a queued task waits before being treated as one priority level higher,
+    /// see `PythonTaskQueue::set_aging`.
+    pub fn aging(mut self, aging: Option<Duration>) -> Self {
+        self.aging = aging;
+        self
+    }
+
+    /// Enables or disables the `tracing` events emitted by the pool, which require the `tracing`
+    /// feature. Enabled by default.
+    pub fn logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// Limits how many tasks the worker executes in a row before releasing the GIL, so that
+    /// other threads get a chance to use Python while the queue stays busy.
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        self.worker.max_batch = Some(max_batch.max(1));
+        self
+    }
+
+    /// Enables or disables the interruption of tasks still running when their deadline passes.
+    /// Enabled by default.
+    pub fn interrupt_overdue(mut self, interrupt_overdue: bool) -> Self {
+        self.worker.interrupt_overdue = interrupt_overdue;
+        self
+    }
+
+    /// Sets how long the pool waits before restarting a worker that exited unexpectedly, the
+    /// wait doubling after every restart that fails. With `None`, the worker isn't restarted and
+    /// the queue is closed instead. 100 ms by default.
+    pub fn restart_backoff(mut self, restart_backoff: Option<Duration>) -> Self {
+        self.worker.restart_backoff = restart_backoff;
+        self
+    }
+
+    /// Appends a directory to `sys.path` before the pool starts.
+    pub fn sys_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.worker.sys_path.push(dir.into());
+        self
+    }
+
+    /// Imports a module before the pool starts, so that the first task using it doesn't pay for
+    /// the import. The pool fails to start if the import fails.
+    pub fn preload(mut self, module: impl Into<String>) -> Self {
+        self.worker.preload.push(module.into());
+        self
+    }
+
+    /// Sets a variable in `os.environ` before the pool starts.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.worker.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the queue the pool will process.
+    pub fn queue(&self) -> PythonTaskQueue {
+        match &self.name {
+            Some(name) => PythonTaskQueue::named(name),
+            None => CLIENT_PYTHON_PROCESS_QUEUE.clone(),
+        }
+    }
+
+    /// Configures the queue and starts a worker processing it.
+    ///
+    /// Returns an error if the queue is already processed by another worker, in which case the
+    /// queue is left as it is, or if the interpreter could not be prepared, e.g. because a
+    /// preloaded module failed to import.
+    pub fn start(self) -> MyResult<PythonPool> {
+        let (capacity, overflow, aging, logging) =
+            (self.capacity, self.overflow, self.aging, self.logging);
+        PythonPool::start_with(&self.queue(), self.worker, |queue| {
+            queue.set_capacity(capacity, overflow);
+            queue.set_aging(aging);
+            queue.set_logging(logging);
+        })
+    }
+}
+
+/// Parses comma-separated `KEY=VALUE` pairs, the value of the environment variable `key`.
+fn parse_env_pairs(key: &str, pairs: &str) -> MyResult<BTreeMap<String, String>> {
+    pairs
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(PythonTaskError::OtherError(format!(
+                "Invalid value for {}: expected KEY=VALUE, got {:?}",
+                key, pair
+            ))),
+        })
+        .collect()
+}
+
+/// Parses the environment variable `key` as returned by `lookup`, `None` if it isn't set.
+fn env_var<T>(lookup: impl Fn(&str) -> Option<OsString>, key: &str) -> MyResult<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match lookup(key).map(OsString::into_string) {
+        Some(Ok(value)) => value.trim().parse().map(Some).map_err(|err| {
+            PythonTaskError::OtherError(format!("Invalid value for {}: {}", key, err))
+        }),
+        None => Ok(None),
+        Some(Err(value)) => Err(PythonTaskError::OtherError(format!(
+            "Invalid value for {}: {}",
+            key,
+            std::env::VarError::NotUnicode(value)
+        ))),
+    }
+}
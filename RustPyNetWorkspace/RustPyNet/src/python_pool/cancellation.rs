@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::PyModule;
+use pyo3::wrap_pyfunction;
+
+thread_local! {
+    /// Cancellation flag of the task currently executing on this worker thread.
+    static CURRENT_TASK_CANCELLED: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Returns whether cancellation of the task currently executing was requested through its
+/// `TaskHandle`.
+///
+/// Long running task bodies can check this to stop early. Outside of a task it returns `false`.
+/// Python code run by a task can check the same flag with `rustpynet.cancelled()`.
+pub fn is_cancelled() -> bool {
+    CURRENT_TASK_CANCELLED.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    })
+}
+
+/// Makes `flag` the cancellation flag of the current thread until the guard is dropped.
+pub(crate) fn enter(flag: Arc<AtomicBool>) -> CancellationGuard {
+    CURRENT_TASK_CANCELLED.with(|current| *current.borrow_mut() = Some(flag));
+    CancellationGuard
+}
+
+pub(crate) struct CancellationGuard;
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        CURRENT_TASK_CANCELLED.with(|current| *current.borrow_mut() = None);
+    }
+}
+
+/// Python side of `is_cancelled`.
+#[pyfunction]
+fn cancelled() -> bool {
+    is_cancelled()
+}
+
+/// Registers the `rustpynet` module in `sys.modules`, so that task code can `import rustpynet`.
+pub(crate) fn register_python_module(py: Python) -> PyResult<()> {
+    let module = PyModule::new(py, "rustpynet")?;
+    module.add_function(wrap_pyfunction!(cancelled, module)?)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("rustpynet", module)?;
+    Ok(())
+}
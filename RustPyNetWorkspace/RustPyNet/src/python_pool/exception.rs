@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use pyo3::exceptions::PyBaseException;
+use pyo3::prelude::*;
+use pyo3::AsPyPointer;
+
+/// A frame of the traceback of a Python exception, as reported by `traceback.extract_tb`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracebackFrame {
+    pub filename: String,
+    pub line: usize,
+    /// Name of the function, or `<module>` for code at the top level of a module.
+    pub function: String,
+    /// The source line, when the file is available.
+    pub source: Option<String>,
+}
+
+/// The details of an exception raised by the Python code of a task.
+///
+/// Formatting the exception with `Display` renders it the way Python prints uncaught exceptions,
+/// chained exceptions and traceback included.
+///
+/// Converting the exception back into a `PyErr` re-raises the original Python exception object.
+#[derive(Clone, Debug)]
+pub struct PythonException {
+    /// Module the exception type is defined in, `builtins` for the built-in exceptions.
+    pub module: String,
+    /// Name of the exception type within its module, such as `ZeroDivisionError`.
+    pub name: String,
+    /// `str()` of the exception.
+    pub message: String,
+    /// `repr()` of each of the exception arguments.
+    pub args: Vec<String>,
+    /// Frames from the outermost call to the one where the exception was raised.
+    pub traceback: Vec<TracebackFrame>,
+    /// The exception this one was raised from, with `raise ... from cause`.
+    pub cause: Option<Box<PythonException>>,
+    /// The exception that was being handled when this one was raised, unless it was suppressed
+    /// with `raise ... from`.
+    pub context: Option<Box<PythonException>>,
+    /// The exception object itself, when it was collected from a `PyErr`.
+    value: Option<Py<PyBaseException>>,
+}
+
+impl PythonException {
+    /// Collects the details of `err`, including the exceptions it is chained to.
+    pub fn from_pyerr(py: Python, err: &PyErr) -> Self {
+        let mut exception = Self::from_value(py, err.pvalue(py), &mut HashSet::new());
+        // The traceback of an exception that was never caught in Python is only held by the
+        // `PyErr`, not by the exception's `__traceback__`.
+        if exception.traceback.is_empty() {
+            if let Some(traceback) = err.ptraceback(py) {
+                exception.traceback = extract_traceback(py, traceback).unwrap_or_default();
+            }
+        }
+        exception.value = Some(err.pvalue(py).into());
+        exception
+    }
+
+    /// The module and name of the exception type, such as `builtins.KeyError`.
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.module, self.name)
+    }
+
+    /// Whether the exception type is `name`, given either as a plain or a qualified name.
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name || self.qualified_name() == name
+    }
+
+    fn from_value(py: Python, value: &PyAny, seen: &mut HashSet<usize>) -> Self {
+        seen.insert(value.as_ptr() as usize);
+
+        let exception_type = value.get_type();
+        let module = exception_type
+            .getattr("__module__")
+            .and_then(|module| module.extract())
+            .unwrap_or_else(|_| "builtins".to_string());
+        let name = exception_type
+            .getattr("__qualname__")
+            .and_then(|name| name.extract())
+            .or_else(|_| exception_type.name().map(str::to_string))
+            .unwrap_or_else(|_| "<unknown>".to_string());
+        let message = value
+            .str()
+            .map(|message| message.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let args = value
+            .getattr("args")
+            .and_then(|args| args.extract::<Vec<&PyAny>>())
+            .map(|args| {
+                args.into_iter()
+                    .map(|arg| {
+                        arg.repr()
+                            .map(|repr| repr.to_string_lossy().into_owned())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let traceback = value
+            .getattr("__traceback__")
+            .and_then(|traceback| extract_traceback(py, traceback))
+            .unwrap_or_default();
+
+        let cause = chained(py, value, "__cause__", seen);
+        let suppress_context = value
+            .getattr("__suppress_context__")
+            .and_then(|suppress| suppress.extract())
+            .unwrap_or(false);
+        let context = if suppress_context {
+            None
+        } else {
+            chained(py, value, "__context__", seen)
+        };
+
+        Self {
+            module,
+            name,
+            message,
+            args,
+            traceback,
+            cause,
+            context,
+            value: None,
+        }
+    }
+
+    /// Formats the exception type and message, like the last line of a Python traceback.
+    pub(crate) fn fmt_summary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.module.as_str() {
+            "builtins" | "__main__" => write!(f, "{}", self.name)?,
+            module => write!(f, "{}.{}", module, self.name)?,
+        }
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the exception stored in the `attr` attribute of `value`, skipping the exceptions already
+/// visited since chains can form cycles.
+fn chained(
+    py: Python,
+    value: &PyAny,
+    attr: &str,
+    seen: &mut HashSet<usize>,
+) -> Option<Box<PythonException>> {
+    let exception = value.getattr(attr).ok()?;
+    if exception.is_none() || seen.contains(&(exception.as_ptr() as usize)) {
+        return None;
+    }
+    Some(Box::new(PythonException::from_value(py, exception, seen)))
+}
+
+fn extract_traceback(py: Python, traceback: &PyAny) -> PyResult<Vec<TracebackFrame>> {
+    if traceback.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let frames = py
+        .import("traceback")?
+        .call_method1("extract_tb", (traceback,))?;
+    frames
+        .iter()?
+        .map(|frame| {
+            let frame = frame?;
+            Ok(TracebackFrame {
+                filename: frame.getattr("filename")?.extract()?,
+                line: frame
+                    .getattr("lineno")?
+                    .extract::<Option<usize>>()?
+                    .unwrap_or(0),
+                function: frame.getattr("name")?.extract()?,
+                source: frame
+                    .getattr("line")?
+                    .extract::<Option<String>>()?
+                    .filter(|source| !source.is_empty()),
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for PythonException {
+    /// Formats the exception like Python's `traceback.format_exception`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(cause) = &self.cause {
+            write!(
+                f,
+                "{}\n\nThe above exception was the direct cause of the following exception:\n\n",
+                cause
+            )?;
+        } else if let Some(context) = &self.context {
+            write!(
+                f,
+                "{}\n\nDuring handling of the above exception, another exception occurred:\n\n",
+                context
+            )?;
+        }
+
+        if !self.traceback.is_empty() {
+            writeln!(f, "Traceback (most recent call last):")?;
+            for frame in &self.traceback {
+                writeln!(
+                    f,
+                    "  File \"{}\", line {}, in {}",
+                    frame.filename, frame.line, frame.function
+                )?;
+                if let Some(source) = &frame.source {
+                    writeln!(f, "    {}", source)?;
+                }
+            }
+        }
+
+        self.fmt_summary(f)
+    }
+}
+
+impl std::error::Error for PythonException {
+    /// The exception this one was raised from, or else the one being handled when it was raised.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .or(self.context.as_deref())
+            .map(|exception| exception as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<PythonException> for PyErr {
+    /// Re-raises the original exception object, or a `RuntimeError` describing the exception if
+    /// it wasn't collected from a `PyErr`.
+    fn from(exception: PythonException) -> PyErr {
+        match &exception.value {
+            Some(value) => Python::with_gil(|py| PyErr::from_instance(value.as_ref(py))),
+            None => pyo3::exceptions::PyRuntimeError::new_err(exception.to_string()),
+        }
+    }
+}
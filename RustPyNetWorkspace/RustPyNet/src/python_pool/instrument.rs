@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use crate::python_pool::pool::{MyResult, PythonTaskError, PythonTaskResult, TaskPriority};
+
+/// The `tracing` span covering a task, from the moment the worker takes it out of the queue.
+///
+/// The span carries the task id, the function name, the priority and how long the task waited
+/// in the queue. When the task ends, its execution time and outcome are recorded on the span and
+/// reported by a `Python task finished` event. Without the `tracing` feature nothing is recorded.
+pub(crate) struct TaskSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    started: Instant,
+}
+
+impl TaskSpan {
+    pub(crate) fn new(
+        enabled: bool,
+        id: u64,
+        function: &str,
+        priority: TaskPriority,
+        queue_wait: Duration,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = if enabled {
+            tracing::debug_span!(
+                "python_task",
+                task_id = id,
+                function,
+                priority = ?priority,
+                queue_wait_us = queue_wait.as_micros() as u64,
+                execution_us = tracing::field::Empty,
+                outcome = tracing::field::Empty,
+            )
+        } else {
+            tracing::Span::none()
+        };
+        #[cfg(not(feature = "tracing"))]
+        let _ = (enabled, id, function, priority, queue_wait);
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span,
+            started: Instant::now(),
+        }
+    }
+
+    /// Runs `f` inside the span, so that events emitted by the task are attributed to it.
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        return f();
+    }
+
+    /// Records how the task ended and how long it ran.
+    pub(crate) fn finish(self, result: &MyResult<PythonTaskResult>) {
+        let execution = self.started.elapsed();
+        let outcome = outcome(result);
+        #[cfg(feature = "tracing")]
+        {
+            let execution_us = execution.as_micros() as u64;
+            self.span.record("execution_us", execution_us);
+            self.span.record("outcome", outcome);
+            tracing::debug!(
+                parent: &self.span,
+                execution_us,
+                outcome,
+                "Python task finished"
+            );
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (execution, outcome);
+    }
+}
+
+/// A short label for how a task ended, as recorded in the `outcome` field of its span.
+fn outcome(result: &MyResult<PythonTaskResult>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(PythonTaskError::PythonError(_)) => "python_error",
+        Err(PythonTaskError::Cancelled) => "cancelled",
+        Err(PythonTaskError::Timeout) => "timeout",
+        Err(PythonTaskError::Interrupted) => "interrupted",
+        Err(PythonTaskError::Panicked(_)) => "panicked",
+        Err(_) => "error",
+    }
+}
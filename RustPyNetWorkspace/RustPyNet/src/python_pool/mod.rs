@@ -1 +1,32 @@
+/// Emits a `tracing` event at the given level when the logging of the pool is enabled.
+///
+/// Without the `tracing` feature nothing is emitted, but the arguments are still type-checked
+/// so that the values only used for logging don't trigger unused warnings.
+macro_rules! log {
+    ($enabled:expr, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        {
+            if $enabled {
+                tracing::$level!($($arg)+);
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = $enabled;
+            if false {
+                let _ = format_args!($($arg)+);
+            }
+        }
+    }};
+}
+
+pub mod builder;
+pub mod cancellation;
+pub mod exception;
+mod instrument;
 pub mod pool;
+mod sync;
+pub mod task_handle;
+mod timer;
+pub mod typed;
+mod watchdog;
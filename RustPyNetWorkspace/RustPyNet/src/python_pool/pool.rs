@@ -1,96 +1,150 @@
-use lazy_static::lazy_static;
 use pyo3::prelude::*;
+use std::any::Any;
 use std::collections::HashMap;
-use std::collections::VecDeque;
-use std::sync::mpsc::Sender;
-use std::sync::Condvar;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use rand::distributions::Alphanumeric;
-use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
 
-use std::fmt::{self, format};
-
-use pyo3::types::{IntoPyDict, PyDict, PyList, PyString, PyTuple};
+use pyo3::types::{PyDict, PyList, PyString};
 use pyo3::{Python, ToPyObject};
 
-use crate::CLIENT_PYTHON_PROCESS_QUEUE;
-
-/// Attempts to lock the provided Python queue and returns it.
-///
-/// This macro will repeatedly try to lock the provided queue until it succeeds.
-/// It will sleep for a random duration between attempts.
-macro_rules! acquire_python_queue {
-    ($queue:expr) => {{
-        let mut acquired = false;
-        let mut q = None;
-        while !acquired {
-            match $queue.try_lock() {
-                Ok(guard) => {
-                    acquired = true;
-                    q = Some(guard);
-                }
-                Err(_) => {
-                    let sleep_duration =
-                        std::time::Duration::from_millis(rand::random::<u64>() % 1000);
-                    println!("Not being able to lock on Pool!");
-                    std::thread::sleep(sleep_duration);
-                }
-            }
-        }
-
-        println!("Locked Python Queue in python pool");
-
-        q.unwrap()
-    }};
-}
-
-/// Executes the provided code block with the provided Python queue.
-///
-/// This macro will try to lock the queue and execute the code block with it.
-/// It will return the result of the code block execution.
-macro_rules! with_python_queue {
-    ($queue:expr, $code:expr) => {{
-        let mut acquired = false;
-        let mut result: Option<_> = None; // Initialize result as None
-        while !acquired {
-            match $queue.try_lock() {
-                Ok(mut guard) => {
-                    acquired = true;
-                    result = Some($code(&mut *guard));
-                }
-                Err(e) => {
-                    let sleep_duration =
-                        std::time::Duration::from_millis(rand::random::<u64>() % 100);
-                    println!("Not being able to lock on Pool! Err {}", e);
-                    std::thread::sleep(sleep_duration);
-                }
-            }
-        }
-        result.expect("Failed to acquire python queue and execute code")
-    }};
-}
+use crate::python_pool::builder::WorkerOptions;
+use crate::python_pool::cancellation;
+use crate::python_pool::exception::PythonException;
+use crate::python_pool::instrument::TaskSpan;
+use crate::python_pool::sync::Recover;
+use crate::python_pool::task_handle::{self, TaskCompleter, TaskHandle, TypedTaskHandle};
+use crate::python_pool::timer;
+use crate::python_pool::typed::{report_completion, FnTask};
+use crate::python_pool::watchdog::Watchdog;
+use crate::{CLIENT_PYTHON_PROCESS_QUEUE, NAMED_PYTHON_PROCESS_QUEUES};
 
 /// Represents various errors that can occur while processing Python tasks.
 ///
 /// This enum encapsulates the different types of errors that might be encountered
 /// when interfacing with Python through the `pyo3` crate. It provides a structured way
 /// to handle these errors in Rust.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum PythonTaskError {
-    /// Represents a generic Python error with a given message.
-    PythonError(String),
+    /// Represents an exception raised by the Python code of the task.
+    PythonError(Box<PythonException>),
     /// Indicates that an unsupported number type was encountered.
     UnsupportedNumberType,
     /// Indicates that an unsupported value type was encountered.
     UnsupportedValueType,
     /// Represents any other error with a given message.
     OtherError(String),
+    /// Indicates that the pool was shut down before the task could run.
+    PoolShutdown,
+    /// Indicates that the result was not available before the caller's timeout elapsed.
+    Timeout,
+    /// Indicates that the task was cancelled through its `TaskHandle` before it ran.
+    Cancelled,
+    /// Indicates that the queue was at capacity and its overflow policy is
+    /// `OverflowPolicy::FailFast`.
+    QueueFull,
+    /// Indicates that the task was dropped from a full queue to make room for a newer one,
+    /// because its overflow policy is `OverflowPolicy::DropOldest`.
+    Evicted,
+    /// Indicates that the task was still running when its deadline passed and was interrupted
+    /// by the pool, so that the worker could move on to the next task.
+    Interrupted,
+    /// Indicates that the task panicked, with the message of the panic. The worker survives the
+    /// panic and continues with the next task.
+    Panicked(String),
+    /// Indicates that the worker thread of the pool failed while running the task. The pool
+    /// restarts its worker, see `PythonPoolBuilder::restart_backoff`.
+    WorkerFailed,
     // Add other error variants as needed
 }
 
+impl fmt::Display for PythonTaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PythonTaskError::PythonError(exception) => exception.fmt_summary(f),
+            PythonTaskError::UnsupportedNumberType => write!(f, "unsupported number type"),
+            PythonTaskError::UnsupportedValueType => write!(f, "unsupported value type"),
+            PythonTaskError::OtherError(message) => write!(f, "{}", message),
+            PythonTaskError::PoolShutdown => {
+                write!(f, "the Python pool was shut down before the task could run")
+            }
+            PythonTaskError::Timeout => write!(f, "the Python task timed out"),
+            PythonTaskError::Cancelled => write!(f, "the Python task was cancelled"),
+            PythonTaskError::QueueFull => write!(f, "the Python task queue is full"),
+            PythonTaskError::Evicted => {
+                write!(f, "the Python task was evicted from a full queue")
+            }
+            PythonTaskError::Interrupted => {
+                write!(
+                    f,
+                    "the Python task was interrupted after its deadline passed"
+                )
+            }
+            PythonTaskError::Panicked(message) => {
+                write!(f, "the Python task panicked: {}", message)
+            }
+            PythonTaskError::WorkerFailed => {
+                write!(f, "the Python worker failed while running the task")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PythonTaskError {
+    /// The Python exception, with its own chain of causes, for `PythonError`.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PythonTaskError::PythonError(exception) => Some(exception.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<PyErr> for PythonTaskError {
+    /// Collects the details of the exception, so that `?` can be used on `PyResult` values in
+    /// functions returning `MyResult`.
+    fn from(err: PyErr) -> Self {
+        Python::with_gil(|py| {
+            PythonTaskError::PythonError(Box::new(PythonException::from_pyerr(py, &err)))
+        })
+    }
+}
+
+impl From<PythonTaskError> for PyErr {
+    /// Converts the error into a Python exception, so that it can be raised back into Python
+    /// code. A `PythonError` re-raises the original exception.
+    fn from(err: PythonTaskError) -> PyErr {
+        use pyo3::exceptions::asyncio::CancelledError;
+        use pyo3::exceptions::{PyRuntimeError, PyTimeoutError, PyTypeError};
+
+        match err {
+            PythonTaskError::PythonError(exception) => (*exception).into(),
+            PythonTaskError::UnsupportedNumberType | PythonTaskError::UnsupportedValueType => {
+                PyTypeError::new_err(err.to_string())
+            }
+            PythonTaskError::Timeout | PythonTaskError::Interrupted => {
+                PyTimeoutError::new_err(err.to_string())
+            }
+            PythonTaskError::Cancelled => CancelledError::new_err(err.to_string()),
+            PythonTaskError::OtherError(_)
+            | PythonTaskError::PoolShutdown
+            | PythonTaskError::QueueFull
+            | PythonTaskError::Evicted
+            | PythonTaskError::Panicked(_)
+            | PythonTaskError::WorkerFailed => PyRuntimeError::new_err(err.to_string()),
+        }
+    }
+}
+
 /// Represents the possible results returned by a Python task.
 ///
 /// This enum models various data types and structures that
@@ -259,12 +313,18 @@ pub trait TaskQueue {
 }
 
 /// A trait representing tasks that can be executed in a Python context.
+///
+/// The task returns its result, which the pool delivers to the `TaskHandle` of the task. Every
+/// enqueued task resolves exactly once: with the value returned by `execute`, or with an error
+/// if it is cancelled, times out, or is dropped without being executed.
 pub trait PythonTask {
-    fn execute(
-        &self,
-        py: Python,
-        tx: Sender<MyResult<PythonTaskResult>>,
-    ) -> MyResult<PythonTaskResult>;
+    fn execute(&self, py: Python) -> MyResult<PythonTaskResult>;
+
+    /// The name the task is reported under in `tracing` spans. Tasks generated by
+    /// `run_with_py` use the name of the decorated function.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
 }
 
 // Implementation for the TaskQueue trait for PythonTaskQueue.
@@ -273,91 +333,1314 @@ impl TaskQueue for PythonTaskQueue {
     // Implement the methods here
 }
 
+/// A task waiting in a `PythonTaskQueue` together with the completer its result is sent through.
+struct QueuedTask {
+    /// Identifies the task in the queue, so its `TaskHandle` can remove it.
+    id: u64,
+    task: Box<dyn PythonTask + Send>,
+    tx: TaskCompleter<PythonTaskResult>,
+    /// When set, the task is skipped if the worker only reaches it after this instant.
+    deadline: Option<Instant>,
+    priority: TaskPriority,
+    /// When the task entered the queue, used to age it towards higher priorities.
+    enqueued_at: Instant,
+}
+
+/// How urgently a task should be executed compared to the other queued tasks.
+///
+/// The worker always takes the task with the highest priority first, and tasks of equal
+/// priority run in the order they were enqueued.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaskPriority {
+    /// Bulk work that can wait behind everything else.
+    Low,
+    #[default]
+    Normal,
+    /// Latency-critical work, such as event handlers.
+    High,
+}
+
+impl TaskPriority {
+    /// The number of priorities, each level being the priority cast to `usize`.
+    const LEVELS: usize = 3;
+}
+
+/// How a single task is enqueued, for `PythonTaskQueue::enqueue_with_options`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaskOptions {
+    /// The task must start within this duration, see `PythonTaskQueue::enqueue_with_timeout`.
+    pub timeout: Option<Duration>,
+    pub priority: TaskPriority,
+}
+
+impl TaskOptions {
+    /// Creates options for a task of normal priority without timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the duration within which the task must start.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the priority of the task.
+    pub fn priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// How long a queued task waits by default before being treated as one priority level higher.
+pub(crate) const DEFAULT_AGING: Duration = Duration::from_secs(1);
+
+/// How long the pool waits by default before restarting a worker that exited unexpectedly.
+pub(crate) const DEFAULT_RESTART_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The longest the pool waits between two attempts to restart its worker, the backoff doubling
+/// after every attempt that fails.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The tasks waiting in a `PythonTaskQueue`, in one FIFO lane per priority.
+struct TaskLanes {
+    /// Indexed by priority level, from `Low` to `High`.
+    lanes: [VecDeque<QueuedTask>; TaskPriority::LEVELS],
+    /// How long a task waits before being treated as one priority level higher, so that
+    /// low-priority tasks still run under a sustained load of higher-priority ones.
+    aging: Option<Duration>,
+    limits: QueueLimits,
+    /// The wakers of the `Enqueue` futures waiting for room in the full queue, by task id.
+    space_wakers: BTreeMap<u64, Waker>,
+}
+
+impl TaskLanes {
+    fn new() -> Self {
+        Self {
+            lanes: Default::default(),
+            aging: Some(DEFAULT_AGING),
+            limits: QueueLimits {
+                capacity: None,
+                overflow: OverflowPolicy::Block,
+            },
+            space_wakers: BTreeMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    fn push(&mut self, queued: QueuedTask) {
+        self.lanes[queued.priority as usize].push_back(queued);
+    }
+
+    /// The priority level `queued` is treated with once aging is accounted for.
+    fn effective_level(&self, queued: &QueuedTask, now: Instant) -> usize {
+        let level = queued.priority as usize;
+        let promotions = match self.aging {
+            Some(aging) if aging.is_zero() => TaskPriority::LEVELS,
+            Some(aging) => {
+                (now.saturating_duration_since(queued.enqueued_at).as_nanos() / aging.as_nanos())
+                    as usize
+            }
+            None => 0,
+        };
+        level
+            .saturating_add(promotions)
+            .min(TaskPriority::LEVELS - 1)
+    }
+
+    /// Takes the task with the highest effective priority, the oldest one on ties.
+    fn pop(&mut self) -> Option<QueuedTask> {
+        let now = Instant::now();
+        let mut best: Option<(usize, usize, Instant)> = None;
+        for (lane, tasks) in self.lanes.iter().enumerate() {
+            // The front of a lane is its oldest task, and so the most aged one.
+            if let Some(front) = tasks.front() {
+                let level = self.effective_level(front, now);
+                let better = match best {
+                    None => true,
+                    Some((_, best_level, best_enqueued_at)) => {
+                        level > best_level
+                            || (level == best_level && front.enqueued_at < best_enqueued_at)
+                    }
+                };
+                if better {
+                    best = Some((lane, level, front.enqueued_at));
+                }
+            }
+        }
+        let (lane, _, _) = best?;
+        self.lanes[lane].pop_front()
+    }
+
+    /// Takes the oldest task of the lowest priority, to make room for a new one.
+    fn pop_lowest(&mut self) -> Option<QueuedTask> {
+        self.lanes
+            .iter_mut()
+            .find(|tasks| !tasks.is_empty())?
+            .pop_front()
+    }
+
+    fn remove(&mut self, id: u64) -> Option<QueuedTask> {
+        self.lanes.iter_mut().find_map(|tasks| {
+            let index = tasks.iter().position(|queued| queued.id == id)?;
+            tasks.remove(index)
+        })
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = QueuedTask> + '_ {
+        self.lanes.iter_mut().flat_map(|tasks| tasks.drain(..))
+    }
+}
+
+/// What `PythonTaskQueue::enqueue` does when the queue is at capacity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Block the producer until the worker frees a slot. Tasks enqueued with a timeout stop
+    /// waiting when the timeout elapses, and resolve with `PythonTaskError::Timeout`.
+    #[default]
+    Block,
+    /// Don't queue the task, which immediately resolves with `PythonTaskError::QueueFull`.
+    FailFast,
+    /// Remove the oldest queued task of the lowest priority, which resolves with
+    /// `PythonTaskError::Evicted`, to make room for the new one.
+    DropOldest,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = PythonTaskError;
+
+    /// Parses `block`, `fail_fast` or `drop_oldest`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(OverflowPolicy::Block),
+            "fail_fast" => Ok(OverflowPolicy::FailFast),
+            "drop_oldest" => Ok(OverflowPolicy::DropOldest),
+            _ => Err(PythonTaskError::OtherError(format!(
+                "Unknown overflow policy {:?}, expected \"block\", \"fail_fast\" or \"drop_oldest\".",
+                s
+            ))),
+        }
+    }
+}
+
+/// The capacity of a `PythonTaskQueue` and what happens when it is reached.
+#[derive(Clone, Copy, Debug)]
+struct QueueLimits {
+    /// Maximum number of queued tasks, `None` for an unbounded queue.
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+}
+
 /// Represents a queue of Python tasks that are to be executed.
+///
+/// A queue is unbounded unless created with `with_capacity`, or limited later with
+/// `set_capacity`. Its current depth is available through `len`.
+///
+/// Cloning a queue is cheap and yields a handle to the same underlying tasks, which is how
+/// a `PythonPool` worker shares the queue with the producers enqueuing into it.
+#[derive(Clone)]
 pub struct PythonTaskQueue {
-    tasks: Arc<
-        Mutex<
-            VecDeque<(
-                Box<dyn PythonTask + Send>,
-                std::sync::mpsc::Sender<MyResult<PythonTaskResult>>,
-            )>,
-        >,
-    >,
+    shared: Arc<QueueShared>,
+}
+
+/// The state behind every handle to a `PythonTaskQueue`.
+struct QueueShared {
+    /// The queued tasks and the limits of the queue, behind the only lock of the queue.
+    tasks: Mutex<TaskLanes>,
+    /// Signalled whenever a task is pushed, or when the worker is asked to shut down.
+    available: Condvar,
+    /// Signalled whenever a task leaves the queue, or when the queue is closed, to wake
+    /// producers blocked on a full queue. Async producers are woken through `space_wakers`.
+    space: Condvar,
+    /// The number of producers waiting for room, blocked on `space` or registered in
+    /// `space_wakers`. Only changed with the `tasks` lock held.
+    blocked: AtomicUsize,
+    /// Whether new tasks are accepted. Only flipped while holding the `tasks` lock so that
+    /// no task can slip in after a shutdown has collected the leftovers.
+    accepting: AtomicBool,
+    /// Whether a worker is currently processing this queue.
+    attached: AtomicBool,
+    /// Whether the queue and its worker emit `tracing` events.
+    logging: Arc<AtomicBool>,
+    next_id: AtomicU64,
 }
 
 impl PythonTaskQueue {
     /// Creates a new empty PythonTaskQueue.
     pub fn new() -> Self {
         Self {
-            tasks: Arc::new(Mutex::new(VecDeque::new())),
+            shared: Arc::new(QueueShared {
+                tasks: Mutex::new(TaskLanes::new()),
+                available: Condvar::new(),
+                space: Condvar::new(),
+                blocked: AtomicUsize::new(0),
+                accepting: AtomicBool::new(true),
+                attached: AtomicBool::new(false),
+                logging: Arc::new(AtomicBool::new(true)),
+                next_id: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Creates a new empty PythonTaskQueue holding at most `capacity` tasks.
+    pub fn with_capacity(capacity: usize, overflow: OverflowPolicy) -> Self {
+        let queue = Self::new();
+        queue.set_capacity(Some(capacity), overflow);
+        queue
+    }
+
+    /// Returns the queue registered under `name`, creating an empty one on first use.
+    ///
+    /// Named queues let independent subsystems each have their own pool, started with
+    /// `PythonPool::start_named`, so slow Python code in one of them doesn't hold up the tasks
+    /// of the others. Functions are routed to a named queue with `#[run_with_py(pool = "name")]`.
+    ///
+    /// The lookup goes through a global lock, so frequent producers should keep the returned
+    /// queue instead of looking it up for every task, as `run_with_py` functions do.
+    pub fn named(name: &str) -> Self {
+        NAMED_PYTHON_PROCESS_QUEUES
+            .lock()
+            .recover()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Changes the capacity of the queue, `None` making it unbounded.
+    ///
+    /// Tasks already queued beyond a reduced capacity are kept.
+    pub fn set_capacity(&self, capacity: Option<usize>, overflow: OverflowPolicy) {
+        let mut tasks = self.shared.tasks.lock().recover();
+        tasks.limits = QueueLimits { capacity, overflow };
+        // Producers blocked on the previous capacity re-check the new one.
+        self.shared.space.notify_all();
+        self.release_space(tasks);
+    }
+
+    /// Returns the capacity of the queue, `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.shared.tasks.lock().recover().limits.capacity
+    }
+
+    /// Changes how long a queued task waits before being treated as one priority level higher,
+    /// `None` disabling aging so that lower priorities only run once higher ones are drained.
+    ///
+    /// Tasks age by one level per second by default. A zero interval lifts every task to the
+    /// highest priority right away, so that the queue runs its tasks in the order they were
+    /// enqueued.
+    pub fn set_aging(&self, aging: Option<Duration>) {
+        self.shared.tasks.lock().recover().aging = aging;
+    }
+
+    /// Enables or disables the `tracing` events emitted by the queue and the pool processing it.
+    ///
+    /// Events are only emitted when the `tracing` feature is enabled.
+    pub fn set_logging(&self, logging: bool) {
+        self.shared.logging.store(logging, Ordering::Relaxed);
+    }
+
+    fn logging(&self) -> bool {
+        self.shared.logging.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of tasks waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.shared.tasks.lock().recover().len()
+    }
+
+    /// Returns whether no task is waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of producers waiting for room in the full queue, see
+    /// `OverflowPolicy::Block`, including the pending `Enqueue` futures of async producers.
+    pub fn blocked_producers(&self) -> usize {
+        self.shared.blocked.load(Ordering::SeqCst)
+    }
+
+    /// Adds a task to the queue and returns a `TaskHandle` to follow, cancel, or get the result
+    /// of the task.
+    ///
+    /// The handle can be passed to `wait_for_result` to block on the result, or awaited
+    /// from async code.
+    ///
+    /// If the pool processing this queue has been shut down, the task is not queued and the
+    /// handle immediately yields `PythonTaskError::PoolShutdown`. If the queue is full, the
+    /// outcome depends on its `OverflowPolicy`.
+    pub fn enqueue(&self, task: Box<dyn PythonTask + Send>) -> TaskHandle<PythonTaskResult> {
+        self.enqueue_with_options(task, TaskOptions::new())
+    }
+
+    /// Adds a task to the queue that must start within `timeout`, and returns a `TaskHandle`
+    /// for it.
+    ///
+    /// If the worker only reaches the task after `timeout` has elapsed, the task is not executed
+    /// and the handle yields `PythonTaskError::Timeout`.
+    pub fn enqueue_with_timeout(
+        &self,
+        task: Box<dyn PythonTask + Send>,
+        timeout: Duration,
+    ) -> TaskHandle<PythonTaskResult> {
+        self.enqueue_with_options(task, TaskOptions::new().timeout(timeout))
+    }
+
+    /// Adds a task to the queue with the given priority, and returns a `TaskHandle` for it.
+    pub fn enqueue_with_priority(
+        &self,
+        task: Box<dyn PythonTask + Send>,
+        priority: TaskPriority,
+    ) -> TaskHandle<PythonTaskResult> {
+        self.enqueue_with_options(task, TaskOptions::new().priority(priority))
+    }
+
+    /// Adds a task to the queue as described by `options`, and returns a `TaskHandle` for it.
+    ///
+    /// This blocks the current thread while a full queue has the `OverflowPolicy::Block` policy,
+    /// async code should use `enqueue_async` instead.
+    pub fn enqueue_with_options(
+        &self,
+        task: Box<dyn PythonTask + Send>,
+        options: TaskOptions,
+    ) -> TaskHandle<PythonTaskResult> {
+        let (queued, handle) = self.prepare(task, options);
+        let mut tasks = self.shared.tasks.lock().recover();
+        loop {
+            match self.admit(&mut tasks, queued.deadline) {
+                Ok(true) => break,
+                Ok(false) => {
+                    self.shared.blocked.fetch_add(1, Ordering::SeqCst);
+                    tasks = match queued.deadline {
+                        Some(deadline) => {
+                            let timeout = deadline.saturating_duration_since(Instant::now());
+                            self.shared.space.wait_timeout(tasks, timeout).recover().0
+                        }
+                        None => self.shared.space.wait(tasks).recover(),
+                    };
+                    self.shared.blocked.fetch_sub(1, Ordering::SeqCst);
+                }
+                Err(err) => {
+                    queued.tx.send(Err(err));
+                    return handle;
+                }
+            }
+        }
+        self.push(&mut tasks, queued);
+        handle
+    }
+
+    /// Returns a future adding a task to the queue as described by `options`, and resolving with
+    /// a `TaskHandle` for it once it is queued.
+    ///
+    /// This is the async counterpart of `enqueue_with_options`: while a full queue has the
+    /// `OverflowPolicy::Block` policy, the future waits for room without blocking the thread
+    /// polling it. With a timeout, it stops waiting when the timeout elapses and the handle
+    /// resolves with `PythonTaskError::Timeout`. The other outcomes of `enqueue` are delivered
+    /// through the handle as well.
+    ///
+    /// Dropping the future before it resolves withdraws the task, which is then never queued.
+    pub fn enqueue_async(&self, task: Box<dyn PythonTask + Send>, options: TaskOptions) -> Enqueue {
+        let (queued, handle) = self.prepare(task, options);
+        Enqueue {
+            queue: self.clone(),
+            id: queued.id,
+            pending: Some((queued, handle)),
+            waiting: false,
+            timer: None,
         }
     }
 
-    /// Adds a task to the queue and returns a Receiver to get the result.
-    pub fn enqueue(
+    /// Creates the queue entry of a task and the handle returned for it.
+    fn prepare(
         &self,
         task: Box<dyn PythonTask + Send>,
-    ) -> std::sync::mpsc::Receiver<MyResult<PythonTaskResult>> {
-        let (tx, rx) = std::sync::mpsc::channel();
-        self.tasks.lock().unwrap().push_back((task, tx));
-        println!(
-            "Task enqueued. Total tasks in queue: {}",
-            self.tasks.lock().unwrap().len()
+        options: TaskOptions,
+    ) -> (QueuedTask, TaskHandle<PythonTaskResult>) {
+        let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+        let id = self.shared.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, handle) = task_handle::new(Some(self.clone()), id);
+        let queued = QueuedTask {
+            id,
+            task,
+            tx,
+            deadline,
+            priority: options.priority,
+            enqueued_at: Instant::now(),
+        };
+        (queued, handle)
+    }
+
+    /// Checks whether a task with the given deadline can be pushed, evicting a task first under
+    /// `OverflowPolicy::DropOldest`.
+    ///
+    /// Returns `Ok(false)` if the producer has to wait for room, and the error the task resolves
+    /// with if it can't be queued.
+    fn admit(&self, tasks: &mut TaskLanes, deadline: Option<Instant>) -> MyResult<bool> {
+        if !self.shared.accepting.load(Ordering::SeqCst) {
+            return Err(PythonTaskError::PoolShutdown);
+        }
+
+        let limits = tasks.limits;
+        match limits.capacity {
+            Some(capacity) if tasks.len() >= capacity => match limits.overflow {
+                OverflowPolicy::FailFast => Err(PythonTaskError::QueueFull),
+                OverflowPolicy::DropOldest => match tasks.pop_lowest() {
+                    Some(oldest) => {
+                        oldest.tx.send(Err(PythonTaskError::Evicted));
+                        Ok(true)
+                    }
+                    // A capacity of zero leaves nothing to evict.
+                    None => Err(PythonTaskError::QueueFull),
+                },
+                OverflowPolicy::Block => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        Err(PythonTaskError::Timeout)
+                    } else {
+                        Ok(false)
+                    }
+                }
+            },
+            _ => Ok(true),
+        }
+    }
+
+    /// Pushes a task admitted by `admit`, waking up the worker.
+    fn push(&self, tasks: &mut TaskLanes, mut queued: QueuedTask) {
+        let id = queued.id;
+        queued.enqueued_at = Instant::now();
+        tasks.push(queued);
+        log!(
+            self.logging(),
+            trace,
+            "Task {} enqueued. Total tasks in queue: {}",
+            id,
+            tasks.len()
         );
-        rx // Return the receiver
+        self.shared.available.notify_one();
+    }
+
+    /// Adds a closure to the queue, to be called with the GIL by the worker, and returns a
+    /// `TypedTaskHandle` delivering the value it returns.
+    ///
+    /// This runs Python code without declaring a `run_with_py` function:
+    ///
+    /// ```ignore
+    /// let handle = queue.submit(move |py| {
+    ///     let len: usize = py.eval("len(items)", None, Some(locals))?.extract()?;
+    ///     Ok(len)
+    /// });
+    /// let len = handle.wait()?;
+    /// ```
+    pub fn submit<F, T>(&self, function: F) -> TypedTaskHandle<T>
+    where
+        F: FnOnce(Python) -> PyResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.submit_with_options(function, TaskOptions::new())
+    }
+
+    /// Adds a closure to the queue as described by `options`, see `submit`.
+    pub fn submit_with_options<F, T>(&self, function: F, options: TaskOptions) -> TypedTaskHandle<T>
+    where
+        F: FnOnce(Python) -> PyResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = FnTask::with_report(
+            std::any::type_name::<F>(),
+            move |py| function(py).map_err(PythonTaskError::from),
+            report_completion,
+        );
+        let output = task.output();
+        TypedTaskHandle::new(self.enqueue_with_options(Box::new(task), options), output)
+    }
+
+    /// Removes a task that is still waiting in the queue, returning its completer.
+    pub(crate) fn remove(&self, id: u64) -> Option<TaskCompleter<PythonTaskResult>> {
+        let mut tasks = self.shared.tasks.lock().recover();
+        let queued = tasks.remove(id)?;
+        self.shared.space.notify_one();
+        self.release_space(tasks);
+        Some(queued.tx)
+    }
+
+    /// Takes the next task out of the queue, waking up a producer blocked waiting for space.
+    ///
+    /// Async producers are only woken up by `release_space`, once the lock is released.
+    fn pop(&self, tasks: &mut TaskLanes) -> Option<QueuedTask> {
+        let queued = tasks.pop()?;
+        self.shared.space.notify_one();
+        Some(queued)
+    }
+
+    /// Releases the lock of the queue, then wakes up every `Enqueue` future waiting for space
+    /// so that they check the queue again.
+    ///
+    /// The wakers are called without the lock since waking can run executor code, which may
+    /// poll the future right away.
+    fn release_space(&self, mut tasks: MutexGuard<'_, TaskLanes>) {
+        let wakers = std::mem::take(&mut tasks.space_wakers);
+        drop(tasks);
+        wakers.into_values().for_each(Waker::wake);
     }
 
     /// Waits for and retrieves the result of a Python task execution.
-    pub fn wait_for_result(
-        rx: std::sync::mpsc::Receiver<MyResult<PythonTaskResult>>,
+    pub fn wait_for_result(handle: TaskHandle<PythonTaskResult>) -> MyResult<PythonTaskResult> {
+        handle.wait()
+    }
+
+    /// Waits for and retrieves the result of a Python task execution, giving up after `timeout`.
+    ///
+    /// Returns `PythonTaskError::Timeout` if the result is not available in time. The task is
+    /// then cancelled, so it is removed from the queue if the worker has not reached it yet.
+    pub fn wait_for_result_timeout(
+        handle: TaskHandle<PythonTaskResult>,
+        timeout: Duration,
     ) -> MyResult<PythonTaskResult> {
-        match rx.recv() {
-            Ok(result) => match result {
-                r => r,
-            },
-            Err(recv_error) => Err(PythonTaskError::OtherError(format!(
-                "Failed to receive result from worker thread due to: {}.",
-                recv_error
-            ))),
+        let result = handle.wait_timeout(timeout);
+        if let Err(PythonTaskError::Timeout) = result {
+            handle.cancel();
+        }
+        result
+    }
+
+    /// Stops accepting new tasks and fails every task still waiting in the queue with
+    /// `PythonTaskError::PoolShutdown`, returning how many were rejected.
+    fn close(&self) -> usize {
+        let mut tasks = self.shared.tasks.lock().recover();
+        self.shared.accepting.store(false, Ordering::SeqCst);
+        let abandoned = tasks.len();
+        for queued in tasks.drain() {
+            queued.tx.send(Err(PythonTaskError::PoolShutdown));
         }
+        // Blocked producers wake up to find the queue closed.
+        self.shared.space.notify_all();
+        self.release_space(tasks);
+        abandoned
     }
 }
 
-/// Starts processing Python tasks from the global task queue.
+impl Default for PythonTaskQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A task being added to a `PythonTaskQueue` from async code, returned by
+/// `PythonTaskQueue::enqueue_async`.
 ///
-/// This function will continuously check the global task queue for tasks,
-/// execute them in a Python context, and send back the results.
-pub fn start_processing_host_python_tasks() {
-    println!("Start processing python calls!");
+/// The future resolves with the `TaskHandle` of the task once the task is queued, or once it is
+/// known that it won't be.
+pub struct Enqueue {
+    queue: PythonTaskQueue,
+    id: u64,
+    /// The task and its handle, until the task is queued or rejected.
+    pending: Option<(QueuedTask, TaskHandle<PythonTaskResult>)>,
+    /// Whether the future is counted as a blocked producer of the queue.
+    waiting: bool,
+    /// The waker registered with the timer for the deadline of the task, and its registration.
+    timer: Option<(timer::Registration, Waker)>,
+}
 
-    loop {
-        // Acquire the Python task queue.
-        let tasks_clone = with_python_queue!(
-            CLIENT_PYTHON_PROCESS_QUEUE,
-            |python_queue: &mut PythonTaskQueue| { python_queue.tasks.clone() }
-        );
+impl Enqueue {
+    /// Stops waiting for space, with the lock of the queue held.
+    fn stop_waiting(&mut self, tasks: &mut TaskLanes) {
+        if self.waiting {
+            self.waiting = false;
+            tasks.space_wakers.remove(&self.id);
+            self.queue.shared.blocked.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
 
-        // Check the number of tasks in the queue.
-        let num_tasks = tasks_clone.lock().unwrap().len();
-        if num_tasks > 0 {
-            println!("Number of tasks in queue: {}", num_tasks);
+    /// Withdraws the waker registered with the timer, so that it isn't kept until the deadline.
+    fn withdraw(&mut self) {
+        if let Some((registration, _)) = self.timer.take() {
+            timer::cancel(registration);
+        }
+    }
+}
 
-            // Acquire the GIL and execute the Python tasks.
-            let gil_guard = Python::acquire_gil();
-            let py = gil_guard.python();
+impl Future for Enqueue {
+    type Output = TaskHandle<PythonTaskResult>;
 
-            while let Some((task, tx)) = tasks_clone.lock().unwrap().pop_front() {
-                println!("Executing a task from the queue...");
-                match task.execute(py, tx) {
-                    Ok(_) => println!("Task successfully executed."),
-                    Err(e) => println!("Error executing task: {:?}", e),
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let (queued, _) = this
+            .pending
+            .as_ref()
+            .expect("`Enqueue` polled after completion.");
+        let deadline = queued.deadline;
+        let queue = this.queue.clone();
+        let mut tasks = queue.shared.tasks.lock().recover();
+        match queue.admit(&mut tasks, deadline) {
+            Ok(false) => {
+                tasks.space_wakers.insert(this.id, cx.waker().clone());
+                if !this.waiting {
+                    this.waiting = true;
+                    queue.shared.blocked.fetch_add(1, Ordering::SeqCst);
+                }
+                drop(tasks);
+
+                if let Some(deadline) = deadline {
+                    let registered = this
+                        .timer
+                        .as_ref()
+                        .is_some_and(|(_, waker)| waker.will_wake(cx.waker()));
+                    if !registered {
+                        this.withdraw();
+                        let registration = timer::wake_at(deadline, cx.waker().clone());
+                        this.timer = Some((registration, cx.waker().clone()));
+                    }
                 }
+                Poll::Pending
+            }
+            admitted => {
+                this.stop_waiting(&mut tasks);
+                let (queued, handle) = this
+                    .pending
+                    .take()
+                    .expect("`Enqueue` polled after completion.");
+                match admitted {
+                    Ok(_) => queue.push(&mut tasks, queued),
+                    Err(err) => {
+                        drop(tasks);
+                        queued.tx.send(Err(err));
+                    }
+                }
+                this.withdraw();
+                Poll::Ready(handle)
+            }
+        }
+    }
+}
+
+impl Drop for Enqueue {
+    fn drop(&mut self) {
+        if self.waiting {
+            let queue = self.queue.clone();
+            let mut tasks = queue.shared.tasks.lock().recover();
+            self.stop_waiting(&mut tasks);
+        }
+        self.withdraw();
+    }
+}
+
+/// Controls what happens to the tasks still waiting in the queue when a `PythonPool` is shut down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Keep executing the queued tasks until the queue is empty, then stop the worker.
+    Drain,
+    /// Stop the worker once the task currently executing finishes and fail every queued task
+    /// with `PythonTaskError::PoolShutdown`.
+    Reject,
+}
+
+/// A handle to a worker thread that executes the tasks of a `PythonTaskQueue`.
+///
+/// `PythonPool::start` only returns once the worker thread has acquired the Python interpreter,
+/// so tasks can be submitted right away. The worker keeps running until `shutdown` is called or
+/// the handle is dropped, in which case the queue is drained first.
+///
+/// Tasks enqueued with a timeout are watched while they run: if a task is still executing
+/// Python code when its deadline passes, a `TimeoutError` is raised inside it and its caller
+/// receives `PythonTaskError::Interrupted`, so a runaway task cannot starve the rest of the queue.
+///
+/// The worker is supervised: if it exits unexpectedly, the task it was running fails with
+/// `PythonTaskError::WorkerFailed` and a new worker is started after a backoff, picking up the
+/// tasks still queued. `is_healthy` and `restarts` report on the worker for readiness probes.
+///
+/// # Usage
+///
+/// ```ignore
+/// let pool = PythonPool::start()?;
+///
+/// let result = compute_sum(&PythonTaskContext::None);
+///
+/// let abandoned = pool.shutdown(ShutdownMode::Reject);
+/// ```
+pub struct PythonPool {
+    queue: PythonTaskQueue,
+    shutdown: Arc<Mutex<Option<ShutdownMode>>>,
+    /// The thread joining the worker, which restarts it if it exits before shutdown.
+    supervisor: Option<thread::JoinHandle<()>>,
+    supervision: Arc<Supervision>,
+    watchdog: Watchdog,
+    monitor: Option<thread::JoinHandle<()>>,
+}
+
+/// The state of the worker of a pool, shared with its supervisor thread.
+struct Supervision {
+    /// Whether a worker is currently processing the queue.
+    healthy: AtomicBool,
+    /// How many times the worker was restarted.
+    restarts: AtomicUsize,
+    /// Wakes the supervisor up from its backoff when the pool shuts down.
+    wake: Condvar,
+}
+
+impl PythonPool {
+    /// Starts a worker processing the global `CLIENT_PYTHON_PROCESS_QUEUE`, which is the queue
+    /// used by functions decorated with `run_with_py`.
+    pub fn start() -> MyResult<Self> {
+        let queue = CLIENT_PYTHON_PROCESS_QUEUE.clone();
+        Self::start_on(&queue)
+    }
+
+    /// Starts a worker processing the queue registered under `name`, see `PythonTaskQueue::named`.
+    pub fn start_named(name: &str) -> MyResult<Self> {
+        Self::start_on(&PythonTaskQueue::named(name))
+    }
+
+    /// Starts a worker processing the given queue.
+    ///
+    /// Returns an error if the queue is already processed by another worker, or if the worker
+    /// thread could not acquire the Python interpreter.
+    ///
+    /// Use `PythonPoolBuilder` to configure the worker.
+    pub fn start_on(queue: &PythonTaskQueue) -> MyResult<Self> {
+        Self::start_with(queue, WorkerOptions::default(), |_| {})
+    }
+
+    /// Starts a worker processing the given queue, calling `configure` on the queue first.
+    ///
+    /// The queue is only configured once it is known not to be processed by another worker, so
+    /// that a failed start doesn't change the settings of a running pool.
+    pub(crate) fn start_with(
+        queue: &PythonTaskQueue,
+        options: WorkerOptions,
+        configure: impl FnOnce(&PythonTaskQueue),
+    ) -> MyResult<Self> {
+        if queue.shared.attached.swap(true, Ordering::SeqCst) {
+            return Err(PythonTaskError::OtherError(
+                "The queue is already being processed by another worker.".to_string(),
+            ));
+        }
+        configure(queue);
+
+        {
+            let _tasks = queue.shared.tasks.lock().recover();
+            queue.shared.accepting.store(true, Ordering::SeqCst);
+        }
+
+        let shutdown = Arc::new(Mutex::new(None));
+        let watchdog = Watchdog::new(queue.shared.logging.clone());
+        let logging = queue.logging();
+        let interrupt_overdue = options.interrupt_overdue;
+
+        let worker = match spawn_worker(queue, &shutdown, &watchdog, &options) {
+            Ok(worker) => worker,
+            Err(err) => {
+                queue.shared.attached.store(false, Ordering::SeqCst);
+                return Err(err);
+            }
+        };
 
-                println!("Task executed.");
+        let supervision = Arc::new(Supervision {
+            healthy: AtomicBool::new(true),
+            restarts: AtomicUsize::new(0),
+            wake: Condvar::new(),
+        });
+        let supervisor_queue = queue.clone();
+        let supervisor_shutdown = shutdown.clone();
+        let supervisor_watchdog = watchdog.clone();
+        let supervisor_state = supervision.clone();
+        let spawned = thread::Builder::new()
+            .name("rustpynet-supervisor".to_string())
+            .spawn(move || {
+                supervise(
+                    worker,
+                    &supervisor_queue,
+                    &supervisor_shutdown,
+                    &supervisor_watchdog,
+                    &options,
+                    &supervisor_state,
+                )
+            });
+        let supervisor = match spawned {
+            Ok(supervisor) => supervisor,
+            Err(err) => {
+                // The handle of the worker was dropped along with the closure, so the worker is
+                // stopped through the queue instead of being joined.
+                *shutdown.lock().recover() = Some(ShutdownMode::Reject);
+                queue.close();
+                queue.shared.available.notify_all();
+                queue.shared.attached.store(false, Ordering::SeqCst);
+                return Err(PythonTaskError::OtherError(format!(
+                    "Failed to spawn the Python supervisor thread due to: {}.",
+                    err
+                )));
+            }
+        };
+
+        // Without a monitor thread, running tasks are never interrupted.
+        let monitor = if interrupt_overdue {
+            match watchdog.spawn() {
+                Ok(monitor) => Some(monitor),
+                Err(err) => {
+                    log!(logging, warn, "Running tasks can't be interrupted: {}", err);
+                    None
+                }
             }
         } else {
-            // If no tasks, sleep for a short duration before checking again.
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            None
+        };
+
+        Ok(Self {
+            queue: queue.clone(),
+            shutdown,
+            supervisor: Some(supervisor),
+            supervision,
+            watchdog,
+            monitor,
+        })
+    }
+
+    /// Returns whether a worker is processing the queue, which is the case from `start` until
+    /// `shutdown`, except while a worker that exited unexpectedly is being restarted.
+    pub fn is_healthy(&self) -> bool {
+        self.supervisor.is_some() && self.supervision.healthy.load(Ordering::SeqCst)
+    }
+
+    /// Returns how many times the worker was restarted after exiting unexpectedly.
+    pub fn restarts(&self) -> usize {
+        self.supervision.restarts.load(Ordering::SeqCst)
+    }
+
+    /// Returns the queue processed by this pool.
+    pub fn queue(&self) -> &PythonTaskQueue {
+        &self.queue
+    }
+
+    /// Returns the number of tasks waiting to be executed by this pool.
+    pub fn queue_depth(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Submits a closure to this pool, see `PythonTaskQueue::submit`.
+    pub fn submit<F, T>(&self, function: F) -> TypedTaskHandle<T>
+    where
+        F: FnOnce(Python) -> PyResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.queue.submit(function)
+    }
+
+    /// Submits a closure to this pool and blocks until it returns, with its value or with the
+    /// error it raised.
+    ///
+    /// ```ignore
+    /// let version: String = pool.run(|py| Ok(py.version().to_string()))?;
+    /// ```
+    pub fn run<F, T>(&self, function: F) -> MyResult<T>
+    where
+        F: FnOnce(Python) -> PyResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.submit(function).wait()
+    }
+
+    /// Stops the pool and joins its worker thread.
+    ///
+    /// New tasks are rejected with `PythonTaskError::PoolShutdown` as soon as this is called.
+    /// Depending on `mode`, the tasks already queued are either executed or rejected as well.
+    ///
+    /// # Returns
+    ///
+    /// The number of queued tasks that were abandoned without being executed.
+    pub fn shutdown(mut self, mode: ShutdownMode) -> usize {
+        self.stop(mode)
+    }
+
+    fn stop(&mut self, mode: ShutdownMode) -> usize {
+        let supervisor = match self.supervisor.take() {
+            Some(supervisor) => supervisor,
+            None => return 0,
+        };
+
+        {
+            let tasks = self.queue.shared.tasks.lock().recover();
+            self.queue.shared.accepting.store(false, Ordering::SeqCst);
+            // Producers blocked on a full queue are rejected right away.
+            self.queue.shared.space.notify_all();
+            self.queue.release_space(tasks);
+        }
+        *self.shutdown.lock().recover() = Some(mode);
+        self.supervision.wake.notify_all();
+        {
+            // Taking the lock guarantees the worker is either about to re-check the shutdown
+            // mode or already waiting, so the notification cannot be missed.
+            let _tasks = self.queue.shared.tasks.lock().recover();
+            self.queue.shared.available.notify_all();
+        }
+
+        // The supervisor returns once the worker it watches has stopped.
+        let _ = supervisor.join();
+        self.supervision.healthy.store(false, Ordering::SeqCst);
+
+        self.watchdog.stop();
+        if let Some(monitor) = self.monitor.take() {
+            let _ = monitor.join();
+        }
+
+        // Whatever the worker left behind will never run.
+        let abandoned = self.queue.close();
+        self.queue.shared.attached.store(false, Ordering::SeqCst);
+        abandoned
+    }
+}
+
+impl Drop for PythonPool {
+    /// Drains the queue and joins the worker if the pool was not shut down explicitly.
+    fn drop(&mut self) {
+        self.stop(ShutdownMode::Drain);
+    }
+}
+
+/// Spawns a worker thread processing `queue`, and returns once it is ready to execute tasks.
+///
+/// Returns an error if the thread could not be spawned or the interpreter could not be
+/// prepared, in which case the thread has already exited.
+fn spawn_worker(
+    queue: &PythonTaskQueue,
+    shutdown: &Arc<Mutex<Option<ShutdownMode>>>,
+    watchdog: &Watchdog,
+    options: &WorkerOptions,
+) -> MyResult<thread::JoinHandle<()>> {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let logging = queue.logging();
+
+    let worker_queue = queue.clone();
+    let worker_shutdown = shutdown.clone();
+    let worker_watchdog = watchdog.clone();
+    let options = options.clone();
+    let spawned = thread::Builder::new()
+        .name("rustpynet-worker".to_string())
+        .spawn(move || {
+            pyo3::prepare_freethreaded_python();
+            {
+                // Hold the GIL once before reporting ready, so that the interpreter is
+                // known to be usable from this thread.
+                let gil_guard = Python::acquire_gil();
+                let py = gil_guard.python();
+                if options.interrupt_overdue {
+                    if let Err(err) = worker_watchdog.attach(py) {
+                        log!(
+                            logging,
+                            warn,
+                            "Running tasks can't be interrupted: {:?}",
+                            err
+                        );
+                    }
+                }
+                if let Err(err) = cancellation::register_python_module(py) {
+                    log!(
+                        logging,
+                        warn,
+                        "Failed to register the rustpynet Python module: {:?}",
+                        err
+                    );
+                }
+                if let Err(err) = options.prepare(py) {
+                    let _ = ready_tx.send(Err(PythonTaskError::PythonError(Box::new(
+                        PythonException::from_pyerr(py, &err),
+                    ))));
+                    return;
+                }
+                let _ = ready_tx.send(Ok(()));
+            }
+            process_tasks(
+                &worker_queue,
+                &worker_shutdown,
+                &worker_watchdog,
+                options.max_batch,
+            );
+        })
+        .map_err(|err| {
+            PythonTaskError::OtherError(format!(
+                "Failed to spawn the Python worker thread due to: {}.",
+                err
+            ))
+        })?;
+
+    let ready = ready_rx.recv().unwrap_or_else(|_| {
+        Err(PythonTaskError::OtherError(
+            "The Python worker thread exited before acquiring the interpreter.".to_string(),
+        ))
+    });
+    match ready {
+        Ok(()) => Ok(spawned),
+        Err(err) => {
+            let _ = spawned.join();
+            Err(err)
+        }
+    }
+}
+
+/// Body of the supervisor thread of a pool.
+///
+/// Waits for the worker to exit and, unless the pool is shutting down, starts a new one after
+/// the restart backoff of `options`. Without a backoff the worker is not restarted and the queue
+/// is closed instead, so that callers don't wait for tasks that will never run.
+fn supervise(
+    mut worker: thread::JoinHandle<()>,
+    queue: &PythonTaskQueue,
+    shutdown: &Arc<Mutex<Option<ShutdownMode>>>,
+    watchdog: &Watchdog,
+    options: &WorkerOptions,
+    supervision: &Supervision,
+) {
+    loop {
+        let exited = worker.join();
+        if shutdown.lock().recover().is_some() {
+            if exited.is_err() {
+                log!(
+                    queue.logging(),
+                    error,
+                    "Python worker thread panicked before shutdown!"
+                );
+            }
+            return;
         }
+
+        supervision.healthy.store(false, Ordering::SeqCst);
+        log!(
+            queue.logging(),
+            error,
+            "The Python worker thread exited unexpectedly."
+        );
+        let mut backoff = match options.restart_backoff {
+            Some(backoff) => backoff,
+            None => {
+                queue.close();
+                return;
+            }
+        };
+
+        worker = loop {
+            let mode = supervision
+                .wake
+                .wait_timeout_while(shutdown.lock().recover(), backoff, |mode| mode.is_none())
+                .recover()
+                .0;
+            if mode.is_some() {
+                return;
+            }
+            drop(mode);
+
+            match spawn_worker(queue, shutdown, watchdog, options) {
+                Ok(worker) => break worker,
+                Err(err) => {
+                    log!(
+                        queue.logging(),
+                        warn,
+                        "Failed to restart the Python worker: {}",
+                        err
+                    );
+                    backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+                }
+            }
+        };
+        supervision.restarts.fetch_add(1, Ordering::SeqCst);
+        supervision.healthy.store(true, Ordering::SeqCst);
+        log!(queue.logging(), info, "Restarted the Python worker thread.");
     }
 }
+
+/// Executes the tasks of `queue` until `shutdown` asks the worker to stop.
+///
+/// The worker blocks on the queue's condition variable while there is nothing to do, so an
+/// enqueue wakes it up immediately. The GIL is only held while tasks are being executed.
+fn process_tasks(
+    queue: &PythonTaskQueue,
+    shutdown: &Mutex<Option<ShutdownMode>>,
+    watchdog: &Watchdog,
+    max_batch: Option<usize>,
+) {
+    while let Some(queued) = wait_for_task(queue, shutdown) {
+        // Acquire the GIL and execute the Python tasks.
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        let mut executed = 0;
+        let mut next = Some(queued);
+        while let Some(queued) = next {
+            execute_task(py, queued, watchdog, queue.logging());
+            executed += 1;
+
+            // Keep the GIL while more tasks are ready, without blocking for new ones.
+            next = if *shutdown.lock().recover() == Some(ShutdownMode::Reject)
+                || max_batch.is_some_and(|max_batch| executed >= max_batch)
+            {
+                None
+            } else {
+                let mut tasks = queue.shared.tasks.lock().recover();
+                let next = queue.pop(&mut tasks);
+                queue.release_space(tasks);
+                next
+            };
+        }
+    }
+}
+
+/// Executes a single task and delivers its result to the caller.
+///
+/// Cancelled tasks and tasks whose caller already timed out are skipped, tasks interrupted by
+/// the watchdog report `PythonTaskError::Interrupted`, and tasks that panic report
+/// `PythonTaskError::Panicked`.
+fn execute_task(py: Python, queued: QueuedTask, watchdog: &Watchdog, logging: bool) {
+    let QueuedTask {
+        id,
+        task,
+        tx,
+        deadline,
+        priority,
+        enqueued_at,
+    } = queued;
+    let result_tx = InFlight(Some(tx));
+
+    let span = TaskSpan::new(logging, id, task.name(), priority, enqueued_at.elapsed());
+    let result = span.in_scope(|| {
+        if result_tx.completer().is_cancelled() {
+            return Err(PythonTaskError::Cancelled);
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(PythonTaskError::Timeout);
+        }
+
+        result_tx.completer().set_running();
+        run_task(py, task.as_ref(), result_tx.completer(), deadline, watchdog)
+    });
+    span.finish(&result);
+    result_tx.send(result);
+}
+
+/// The completer of the task the worker is executing, which fails the task with
+/// `PythonTaskError::WorkerFailed` if the worker unwinds before delivering its result.
+struct InFlight(Option<TaskCompleter<PythonTaskResult>>);
+
+impl InFlight {
+    fn completer(&self) -> &TaskCompleter<PythonTaskResult> {
+        self.0
+            .as_ref()
+            .expect("The result of the task was already sent.")
+    }
+
+    fn send(mut self, result: MyResult<PythonTaskResult>) {
+        if let Some(tx) = self.0.take() {
+            tx.send(result);
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(tx) = self.0.take() {
+            tx.send(Err(PythonTaskError::WorkerFailed));
+        }
+    }
+}
+
+/// Runs a task that is due, returning its result.
+fn run_task(
+    py: Python,
+    task: &(dyn PythonTask + Send),
+    result_tx: &TaskCompleter<PythonTaskResult>,
+    deadline: Option<Instant>,
+    watchdog: &Watchdog,
+) -> MyResult<PythonTaskResult> {
+    let (result, interrupted) = {
+        let _cancellation = cancellation::enter(result_tx.cancel_flag());
+        watchdog.begin(py, deadline);
+        // A panicking task must not take the worker down with it, which would leave every
+        // queued task and every later caller waiting forever.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| task.execute(py)))
+            .unwrap_or_else(|payload| Err(PythonTaskError::Panicked(panic_message(&*payload))));
+        (result, watchdog.end(py))
+    };
+
+    // The error is the `TimeoutError` raised by the watchdog, unless the task handled it.
+    if interrupted && result.is_err() {
+        Err(PythonTaskError::Interrupted)
+    } else {
+        result
+    }
+}
+
+/// Extracts the message of a panic, which is a `&str` or a `String` unless the task panicked
+/// with a custom payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "the task panicked with a non-string payload".to_string()
+    }
+}
+
+/// Blocks until a task is available, returning `None` once the worker should stop.
+fn wait_for_task(
+    queue: &PythonTaskQueue,
+    shutdown: &Mutex<Option<ShutdownMode>>,
+) -> Option<QueuedTask> {
+    let mut tasks = queue.shared.tasks.lock().recover();
+    loop {
+        let mode = *shutdown.lock().recover();
+        if mode == Some(ShutdownMode::Reject) {
+            return None;
+        }
+        if let Some(task) = queue.pop(&mut tasks) {
+            queue.release_space(tasks);
+            return Some(task);
+        }
+        if mode == Some(ShutdownMode::Drain) {
+            return None;
+        }
+        tasks = queue.shared.available.wait(tasks).recover();
+    }
+}
+
+/// Starts processing Python tasks from the global task queue.
+///
+/// This function will continuously check the global task queue for tasks,
+/// execute them in a Python context, and send back the results. It never returns
+/// unless another worker is already processing the global queue.
+#[deprecated(
+    note = "use `PythonPool::start`, which can be shut down and waits for the worker to be ready"
+)]
+pub fn start_processing_host_python_tasks() {
+    let queue = CLIENT_PYTHON_PROCESS_QUEUE.clone();
+    log!(queue.logging(), info, "Start processing python calls!");
+
+    if queue.shared.attached.swap(true, Ordering::SeqCst) {
+        log!(
+            queue.logging(),
+            warn,
+            "The global Python queue is already being processed!"
+        );
+        return;
+    }
+
+    {
+        let _tasks = queue.shared.tasks.lock().recover();
+        queue.shared.accepting.store(true, Ordering::SeqCst);
+    }
+
+    {
+        let gil_guard = Python::acquire_gil();
+        if let Err(err) = cancellation::register_python_module(gil_guard.python()) {
+            log!(
+                queue.logging(),
+                warn,
+                "Failed to register the rustpynet Python module: {:?}",
+                err
+            );
+        }
+    }
+
+    // Without a monitor thread, running tasks are never interrupted.
+    let watchdog = Watchdog::new(queue.shared.logging.clone());
+    process_tasks(&queue, &Mutex::new(None), &watchdog, None);
+}
@@ -0,0 +1,17 @@
+use std::sync::{LockResult, PoisonError};
+
+/// Recovers the guard of a poisoned lock instead of panicking.
+///
+/// A lock is poisoned when a thread panics while holding it. None of the locks of the pool is
+/// held while a task runs, and the state they protect is only changed in short sections that
+/// can't leave it half-updated, so failing every later caller over a poisoned lock would only
+/// turn one panic into a stuck process.
+pub(crate) trait Recover<T> {
+    fn recover(self) -> T;
+}
+
+impl<T> Recover<T> for LockResult<T> {
+    fn recover(self) -> T {
+        self.unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,399 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::python_pool::pool::{MyResult, PythonTaskError, PythonTaskQueue, PythonTaskResult};
+use crate::python_pool::sync::Recover;
+use crate::python_pool::timer;
+use crate::python_pool::typed::TypedOutput;
+
+/// The lifecycle stage of an enqueued task, as reported by `TaskHandle::status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// The task is waiting in the queue.
+    Queued,
+    /// The worker is executing the task.
+    Running,
+    /// The task produced a result, successful or not.
+    Done,
+    /// The task was cancelled before it could produce a result.
+    Cancelled,
+}
+
+/// What `TaskHandle::cancel` achieved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The task was removed from the queue without running, and resolves with
+    /// `PythonTaskError::Cancelled`.
+    Removed,
+    /// The task had already left the queue, so only its cancellation flag was set. It may still
+    /// finish with a result, unless it checks the flag and stops early.
+    Flagged,
+    /// The task had already finished, nothing was changed.
+    TooLate,
+}
+
+/// State shared between a `TaskHandle` and the `TaskCompleter` owned by the queue.
+struct Shared<R> {
+    state: Mutex<State<R>>,
+    ready: Condvar,
+    /// Set when cancellation is requested. Running tasks can poll it through `is_cancelled`.
+    cancelled: Arc<AtomicBool>,
+}
+
+struct State<R> {
+    status: TaskStatus,
+    value: Option<MyResult<R>>,
+    /// Whether the result was already handed out by `try_result` or a timed wait.
+    taken: bool,
+    waker: Option<Waker>,
+}
+
+/// Creates the two halves tracking a task: the completer kept next to the task in the queue,
+/// and the handle returned to the caller.
+///
+/// `queue` and `id` identify the task in its queue so that `cancel` can remove it from there.
+pub(crate) fn new<R>(queue: Option<PythonTaskQueue>, id: u64) -> (TaskCompleter<R>, TaskHandle<R>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            status: TaskStatus::Queued,
+            value: None,
+            taken: false,
+            waker: None,
+        }),
+        ready: Condvar::new(),
+        cancelled: Arc::new(AtomicBool::new(false)),
+    });
+
+    (
+        TaskCompleter {
+            shared: Some(shared.clone()),
+        },
+        TaskHandle { shared, queue, id },
+    )
+}
+
+/// The worker side of a task, used to report progress and deliver exactly one result.
+///
+/// Dropping the completer without sending resolves the handle with an error, so a waiting
+/// caller never hangs on a task that was discarded.
+pub(crate) struct TaskCompleter<R> {
+    shared: Option<Arc<Shared<R>>>,
+}
+
+impl<R> TaskCompleter<R> {
+    /// Returns whether cancellation of the task was requested.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.shared
+            .as_ref()
+            .map_or(true, |shared| shared.cancelled.load(Ordering::SeqCst))
+    }
+
+    /// Returns the flag set when cancellation of the task is requested.
+    pub(crate) fn cancel_flag(&self) -> Arc<AtomicBool> {
+        match &self.shared {
+            Some(shared) => shared.cancelled.clone(),
+            None => Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Marks the task as picked up by the worker.
+    pub(crate) fn set_running(&self) {
+        if let Some(shared) = &self.shared {
+            shared.state.lock().recover().status = TaskStatus::Running;
+        }
+    }
+
+    /// Delivers the result to the handle, waking it up if it is waiting.
+    pub(crate) fn send(mut self, value: MyResult<R>) {
+        if let Some(shared) = self.shared.take() {
+            complete(&shared, value);
+        }
+    }
+}
+
+impl<R> Drop for TaskCompleter<R> {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            complete(
+                &shared,
+                Err(PythonTaskError::OtherError(
+                    "The task was dropped before producing a result.".to_string(),
+                )),
+            );
+        }
+    }
+}
+
+fn complete<R>(shared: &Shared<R>, value: MyResult<R>) {
+    let waker = {
+        let mut state = shared.state.lock().recover();
+        state.status = match value {
+            Err(PythonTaskError::Cancelled) => TaskStatus::Cancelled,
+            _ => TaskStatus::Done,
+        };
+        state.value = Some(value);
+        state.waker.take()
+    };
+    shared.ready.notify_all();
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// A handle to an enqueued task, returned by `PythonTaskQueue::enqueue`.
+///
+/// The handle reports the status of the task, can cancel it, and delivers its result: either
+/// by blocking the current thread with `wait`/`wait_timeout`, by polling with `try_result`, or
+/// by awaiting it from async code, since the handle is a `Future` that does not depend on any
+/// particular runtime.
+///
+/// Dropping the handle detaches the task: it still runs, but its result is discarded.
+pub struct TaskHandle<R> {
+    shared: Arc<Shared<R>>,
+    queue: Option<PythonTaskQueue>,
+    id: u64,
+}
+
+impl<R> TaskHandle<R> {
+    /// Returns the current status of the task.
+    pub fn status(&self) -> TaskStatus {
+        self.shared.state.lock().recover().status
+    }
+
+    /// Requests cancellation of the task.
+    ///
+    /// A task still waiting in the queue is removed from it without running, and resolves with
+    /// `PythonTaskError::Cancelled`. A running task can't be stopped from the outside, but its
+    /// cancellation flag is set: Rust code can check it with `is_cancelled()` and Python code
+    /// with `rustpynet.cancelled()`, to stop early.
+    ///
+    /// The returned `CancelOutcome` tells which of these happened, or whether the task had already
+    /// finished.
+    pub fn cancel(&self) -> CancelOutcome {
+        if matches!(self.status(), TaskStatus::Done | TaskStatus::Cancelled) {
+            return CancelOutcome::TooLate;
+        }
+
+        self.shared.cancelled.store(true, Ordering::SeqCst);
+        match self.queue.as_ref().and_then(|queue| queue.remove(self.id)) {
+            Some(completer) => {
+                // Dropping the removed completer would report a dropped task, so report the
+                // cancellation explicitly instead.
+                completer.send(Err(PythonTaskError::Cancelled));
+                CancelOutcome::Removed
+            }
+            None => CancelOutcome::Flagged,
+        }
+    }
+
+    /// Returns the result if the task has finished, without blocking.
+    ///
+    /// The result is handed out only once: later calls, or waiting afterwards, report an error.
+    pub fn try_result(&self) -> Option<MyResult<R>> {
+        let mut state = self.shared.state.lock().recover();
+        take_result(&mut state)
+    }
+
+    /// Blocks the current thread until the result is available.
+    pub fn wait(self) -> MyResult<R> {
+        let mut state = self.shared.state.lock().recover();
+        loop {
+            if let Some(value) = take_result(&mut state) {
+                return value;
+            }
+            state = self.shared.ready.wait(state).recover();
+        }
+    }
+
+    /// Blocks the current thread until the result is available or `timeout` elapses.
+    ///
+    /// Returns `PythonTaskError::Timeout` if the task has not finished in time. The task is left
+    /// untouched, so the caller can keep waiting or `cancel` it.
+    pub fn wait_timeout(&self, timeout: Duration) -> MyResult<R> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.state.lock().recover();
+        loop {
+            if let Some(value) = take_result(&mut state) {
+                return value;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PythonTaskError::Timeout);
+            }
+            state = self
+                .shared
+                .ready
+                .wait_timeout(state, deadline - now)
+                .recover()
+                .0;
+        }
+    }
+
+    /// Returns a future resolving with the result, or with `PythonTaskError::Timeout` if the
+    /// result is not available within `timeout`.
+    ///
+    /// This is the async counterpart of `PythonTaskQueue::wait_for_result_timeout`: on timeout
+    /// the task is cancelled, so it is removed from the queue if the worker has not reached it
+    /// yet. The future resolves even if no pool processes the queue.
+    pub fn with_timeout(self, timeout: Duration) -> WithTimeout<R> {
+        WithTimeout {
+            handle: self,
+            deadline: Instant::now() + timeout,
+            timer: None,
+        }
+    }
+
+    /// Drops the handle, letting the task run without waiting for its result.
+    pub fn detach(self) {}
+}
+
+fn take_result<R>(state: &mut State<R>) -> Option<MyResult<R>> {
+    match state.value.take() {
+        Some(value) => {
+            state.taken = true;
+            Some(value)
+        }
+        None if state.taken => Some(Err(PythonTaskError::OtherError(
+            "The result of the task was already taken.".to_string(),
+        ))),
+        None => None,
+    }
+}
+
+impl<R> Future for TaskHandle<R> {
+    type Output = MyResult<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().recover();
+        match take_result(&mut state) {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A `TaskHandle` awaited with a timeout, returned by `TaskHandle::with_timeout`.
+pub struct WithTimeout<R> {
+    handle: TaskHandle<R>,
+    deadline: Instant,
+    /// The waker last registered with the timer, and its registration.
+    timer: Option<(timer::Registration, Waker)>,
+}
+
+impl<R> WithTimeout<R> {
+    /// Withdraws the waker registered with the timer, so that it isn't kept until the deadline.
+    fn withdraw(&mut self) {
+        if let Some((registration, _)) = self.timer.take() {
+            timer::cancel(registration);
+        }
+    }
+}
+
+impl<R> Future for WithTimeout<R> {
+    type Output = MyResult<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(value) = Pin::new(&mut self.handle).poll(cx) {
+            self.withdraw();
+            return Poll::Ready(value);
+        }
+        if Instant::now() >= self.deadline {
+            self.withdraw();
+            self.handle.cancel();
+            return Poll::Ready(Err(PythonTaskError::Timeout));
+        }
+        let registered = self
+            .timer
+            .as_ref()
+            .is_some_and(|(_, waker)| waker.will_wake(cx.waker()));
+        if !registered {
+            self.withdraw();
+            let registration = timer::wake_at(self.deadline, cx.waker().clone());
+            self.timer = Some((registration, cx.waker().clone()));
+        }
+        Poll::Pending
+    }
+}
+
+impl<R> Drop for WithTimeout<R> {
+    fn drop(&mut self) {
+        self.withdraw();
+    }
+}
+
+/// A handle to a closure submitted with `PythonTaskQueue::submit`, delivering the value the
+/// closure returned.
+///
+/// It behaves like `TaskHandle`, failures of the pool and errors raised by the closure both
+/// being reported as a `PythonTaskError`.
+pub struct TypedTaskHandle<T> {
+    handle: TaskHandle<PythonTaskResult>,
+    output: TypedOutput<MyResult<T>>,
+}
+
+impl<T: Send + 'static> TypedTaskHandle<T> {
+    pub(crate) fn new(
+        handle: TaskHandle<PythonTaskResult>,
+        output: TypedOutput<MyResult<T>>,
+    ) -> Self {
+        Self { handle, output }
+    }
+
+    /// Returns the current status of the task.
+    pub fn status(&self) -> TaskStatus {
+        self.handle.status()
+    }
+
+    /// Requests cancellation of the task, see `TaskHandle::cancel`.
+    pub fn cancel(&self) -> CancelOutcome {
+        self.handle.cancel()
+    }
+
+    /// Returns the result if the task has finished, without blocking.
+    ///
+    /// The result is handed out only once: later calls, or waiting afterwards, report an error.
+    pub fn try_result(&self) -> Option<MyResult<T>> {
+        self.handle
+            .try_result()
+            .map(|received| self.output.clone().finish_with(received, Err))
+    }
+
+    /// Blocks the current thread until the result is available.
+    pub fn wait(self) -> MyResult<T> {
+        let received = self.handle.wait();
+        self.output.finish_with(received, Err)
+    }
+
+    /// Blocks the current thread until the result is available or `timeout` elapses.
+    ///
+    /// Returns `PythonTaskError::Timeout` if the task has not finished in time. The task is left
+    /// untouched, so the caller can keep waiting or `cancel` it.
+    pub fn wait_timeout(&self, timeout: Duration) -> MyResult<T> {
+        match self.handle.wait_timeout(timeout) {
+            Err(PythonTaskError::Timeout) => Err(PythonTaskError::Timeout),
+            received => self.output.clone().finish_with(received, Err),
+        }
+    }
+
+    /// Drops the handle, letting the task run without waiting for its result.
+    pub fn detach(self) {}
+}
+
+impl<T: Send + 'static> Future for TypedTaskHandle<T> {
+    type Output = MyResult<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.handle).poll(cx) {
+            Poll::Ready(received) => Poll::Ready(self.output.clone().finish_with(received, Err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
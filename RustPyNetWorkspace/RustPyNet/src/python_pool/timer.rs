@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::sync::{Condvar, Mutex};
+use std::task::Waker;
+use std::thread;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+
+use crate::python_pool::sync::Recover;
+
+/// Wakes up futures at their deadline, from a single thread shared by all of them and started
+/// on first use.
+///
+/// A future withdraws its deadline with `cancel` once it completes or is dropped, so that the
+/// timer doesn't keep its waker, and whatever the waker keeps alive, until the deadline passes.
+struct Timer {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    /// The wakers to wake up, by deadline and then by registration order.
+    deadlines: BTreeMap<Registration, Waker>,
+    next_id: u64,
+    started: bool,
+}
+
+/// Identifies a waker registered with `wake_at`, to withdraw it with `cancel`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Registration {
+    at: Instant,
+    id: u64,
+}
+
+lazy_static! {
+    static ref TIMER: Timer = Timer {
+        state: Mutex::new(State {
+            deadlines: BTreeMap::new(),
+            next_id: 0,
+            started: false,
+        }),
+        changed: Condvar::new(),
+    };
+}
+
+/// Wakes `waker` up once `at` has passed, unless the returned registration is cancelled first.
+pub(crate) fn wake_at(at: Instant, waker: Waker) -> Registration {
+    let mut state = TIMER.state.lock().recover();
+    let registration = Registration {
+        at,
+        id: state.next_id,
+    };
+    state.next_id += 1;
+    if !state.started {
+        state.started = thread::Builder::new()
+            .name("rustpynet-timer".to_string())
+            .spawn(run)
+            .is_ok();
+        if !state.started {
+            // Without the timer the future is polled again right away, rather than never.
+            drop(state);
+            waker.wake();
+            return registration;
+        }
+    }
+    state.deadlines.insert(registration, waker);
+    TIMER.changed.notify_one();
+    registration
+}
+
+/// Withdraws a waker registered with `wake_at`, which does nothing if it was already woken up.
+pub(crate) fn cancel(registration: Registration) {
+    let waker = TIMER.state.lock().recover().deadlines.remove(&registration);
+    // Dropped without the lock, since dropping a waker can run executor code.
+    drop(waker);
+}
+
+fn run() {
+    let mut state = TIMER.state.lock().recover();
+    loop {
+        let now = Instant::now();
+        let next = state
+            .deadlines
+            .first_key_value()
+            .map(|(registration, _)| registration.at);
+        state = match next {
+            None => TIMER.changed.wait(state).recover(),
+            Some(at) if at > now => TIMER.changed.wait_timeout(state, at - now).recover().0,
+            Some(_) => {
+                let mut due = Vec::new();
+                while let Some(entry) = state.deadlines.first_entry() {
+                    if entry.key().at > now {
+                        break;
+                    }
+                    due.push(entry.remove());
+                }
+                // Waking can run executor code registering new deadlines, so not with the lock.
+                drop(state);
+                due.into_iter().for_each(Waker::wake);
+                TIMER.state.lock().recover()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn cancelled_deadlines_release_their_waker() {
+        let wakes = Arc::new(CountWakes::default());
+        let registration = wake_at(
+            Instant::now() + Duration::from_secs(60),
+            Waker::from(wakes.clone()),
+        );
+        assert_eq!(Arc::strong_count(&wakes), 2);
+
+        cancel(registration);
+        assert_eq!(Arc::strong_count(&wakes), 1);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn due_deadlines_wake_their_waker_once() {
+        let wakes = Arc::new(CountWakes::default());
+        let registration = wake_at(Instant::now(), Waker::from(wakes.clone()));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Arc::strong_count(&wakes) > 1 && Instant::now() < deadline {
+            thread::yield_now();
+        }
+        assert_eq!(Arc::strong_count(&wakes), 1);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        // Cancelling after the wake-up has no effect.
+        cancel(registration);
+    }
+}
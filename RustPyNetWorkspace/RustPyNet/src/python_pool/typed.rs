@@ -0,0 +1,345 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use pyo3::prelude::*;
+
+use crate::python_pool::pool::{MyResult, PythonTask, PythonTaskError, PythonTaskResult};
+use crate::python_pool::sync::Recover;
+
+/// The return types a `run_with_py` function can have.
+///
+/// Any `Result<T, E>` qualifies as long as `T` is `Send + 'static` and `E` can represent the
+/// failures of the pool itself, such as a timeout or a shutdown, through `From<PythonTaskError>`.
+/// This is the case of `PythonTaskError`, of `PyErr`, and of error types like `anyhow::Error` or
+/// `Box<dyn Error + Send + Sync>`. For `?` to work on `PyResult` values in the function body, `E`
+/// must also implement `From<PyErr>`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be returned by a `run_with_py` function",
+    note = "return a `Result<T, E>` where `T: Send + 'static` and `E: From<PythonTaskError>`"
+)]
+pub trait TaskReturn: Send + 'static {
+    /// Wraps a failure of the pool into the return type.
+    fn from_task_error(err: PythonTaskError) -> Self;
+
+    /// The result reported to a `TaskHandle` when the task was enqueued by hand.
+    fn to_task_result(&self) -> MyResult<PythonTaskResult>;
+}
+
+impl<T, E> TaskReturn for Result<T, E>
+where
+    T: Send + 'static,
+    E: From<PythonTaskError> + fmt::Display + Send + 'static,
+{
+    fn from_task_error(err: PythonTaskError) -> Self {
+        Err(E::from(err))
+    }
+
+    fn to_task_result(&self) -> MyResult<PythonTaskResult> {
+        match self {
+            Ok(value) => Ok(value_to_task_result(value)),
+            Err(err) => {
+                let any: &dyn Any = err;
+                if let Some(err) = any.downcast_ref::<PythonTaskError>() {
+                    Err(err.clone())
+                } else if let Some(err) = any.downcast_ref::<PyErr>() {
+                    Err(Python::with_gil(|py| err.clone_ref(py)).into())
+                } else {
+                    Err(PythonTaskError::OtherError(err.to_string()))
+                }
+            }
+        }
+    }
+}
+
+/// Converts the value returned by a `run_with_py` function into the `PythonTaskResult` reported
+/// to the `TaskHandle` of a task enqueued by hand. Calling the function delivers the value itself.
+///
+/// `PythonTaskResult`, booleans, integers, floats and strings are converted, as well as `Option`,
+/// `Vec` and `HashMap<String, _>` of them. Any other value is reported as `PythonTaskResult::None`.
+fn value_to_task_result(value: &dyn Any) -> PythonTaskResult {
+    macro_rules! convert {
+        ($($value:ty),*) => {
+            $(
+                if let Some(value) = value.downcast_ref::<$value>() {
+                    return value.to_task_result();
+                }
+                if let Some(value) = value.downcast_ref::<Option<$value>>() {
+                    return value.to_task_result();
+                }
+                if let Some(value) = value.downcast_ref::<Vec<$value>>() {
+                    return value.to_task_result();
+                }
+                if let Some(value) = value.downcast_ref::<HashMap<String, $value>>() {
+                    return value.to_task_result();
+                }
+            )*
+        };
+    }
+
+    convert!(
+        PythonTaskResult,
+        bool,
+        i8,
+        i16,
+        i32,
+        i64,
+        isize,
+        u8,
+        u16,
+        u32,
+        u64,
+        usize,
+        f32,
+        f64,
+        String
+    );
+    PythonTaskResult::None
+}
+
+/// The values `value_to_task_result` converts.
+trait TaskValue {
+    fn to_task_result(&self) -> PythonTaskResult;
+}
+
+impl TaskValue for PythonTaskResult {
+    fn to_task_result(&self) -> PythonTaskResult {
+        self.clone()
+    }
+}
+
+impl TaskValue for bool {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::Bool(*self)
+    }
+}
+
+macro_rules! impl_task_value_for_ints {
+    ($($int:ty),*) => {
+        $(
+            impl TaskValue for $int {
+                fn to_task_result(&self) -> PythonTaskResult {
+                    match i32::try_from(*self) {
+                        Ok(value) => PythonTaskResult::Int(value),
+                        Err(_) => PythonTaskResult::Float(*self as f64),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_task_value_for_ints!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TaskValue for f32 {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::Float(f64::from(*self))
+    }
+}
+
+impl TaskValue for f64 {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::Float(*self)
+    }
+}
+
+impl TaskValue for String {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::Str(self.clone())
+    }
+}
+
+impl<T: TaskValue> TaskValue for Option<T> {
+    fn to_task_result(&self) -> PythonTaskResult {
+        match self {
+            Some(value) => value.to_task_result(),
+            None => PythonTaskResult::None,
+        }
+    }
+}
+
+impl<T: TaskValue> TaskValue for Vec<T> {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::List(self.iter().map(TaskValue::to_task_result).collect())
+    }
+}
+
+impl<T: TaskValue> TaskValue for HashMap<String, T> {
+    fn to_task_result(&self) -> PythonTaskResult {
+        PythonTaskResult::Map(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.to_task_result()))
+                .collect(),
+        )
+    }
+}
+
+/// Carries the typed result of a `run_with_py` function from the worker to the caller, next
+/// to the untyped result delivered by the queue.
+#[doc(hidden)]
+pub struct TypedOutput<R> {
+    slot: Arc<Mutex<Option<R>>>,
+}
+
+impl<R> Clone for TypedOutput<R> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<R: Send + 'static> Default for TypedOutput<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Send + 'static> TypedOutput<R> {
+    pub fn new() -> Self {
+        Self {
+            slot: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stores the result of the task on the worker, returning `status` for the pool to deliver
+    /// to the untyped handle of the task.
+    pub fn complete_with(
+        &self,
+        result: R,
+        status: MyResult<PythonTaskResult>,
+    ) -> MyResult<PythonTaskResult> {
+        *self.slot.lock().recover() = Some(result);
+        status
+    }
+
+    /// Turns the result received from the queue into the typed result of the task, failures of
+    /// the pool being wrapped with `from_task_error`.
+    ///
+    /// Failures of the pool, such as a timeout or an interruption, take precedence over what the
+    /// task returned.
+    pub fn finish_with(
+        self,
+        received: MyResult<PythonTaskResult>,
+        from_task_error: impl FnOnce(PythonTaskError) -> R,
+    ) -> R {
+        let output = self.slot.lock().recover().take();
+        match (received, output) {
+            (Err(PythonTaskError::Interrupted), _) => from_task_error(PythonTaskError::Interrupted),
+            (_, Some(output)) => output,
+            (Err(err), None) => from_task_error(err),
+            (Ok(_), None) => from_task_error(PythonTaskError::OtherError(
+                "The task finished without producing a result.".to_string(),
+            )),
+        }
+    }
+}
+
+impl<R: TaskReturn> TypedOutput<R> {
+    /// Stores the result of the function body on the worker, returning its untyped version for
+    /// the pool to deliver to handles of tasks enqueued by hand.
+    pub fn complete(&self, result: R) -> MyResult<PythonTaskResult> {
+        let status = result.to_task_result();
+        self.complete_with(result, status)
+    }
+
+    /// Turns the result received from the queue into the typed result of the function.
+    pub fn finish(self, received: MyResult<PythonTaskResult>) -> R {
+        self.finish_with(received, R::from_task_error)
+    }
+}
+
+/// Holds a parameter of a `run_with_py` function that the function body takes by value, until
+/// the worker moves it out to execute the task.
+#[doc(hidden)]
+pub struct TaskArgument<T> {
+    value: Mutex<Option<T>>,
+}
+
+impl<T: Send + 'static> TaskArgument<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: Mutex::new(Some(value)),
+        }
+    }
+
+    /// Moves the value out, which the worker does once when it executes the task.
+    pub fn take(&self) -> T {
+        self.value
+            .lock()
+            .recover()
+            .take()
+            .expect("The arguments of a task can only be taken once.")
+    }
+}
+
+/// A task running a closure with the GIL, which is how closures are submitted and how
+/// `run_with_py` methods send their receiver to the pool.
+#[doc(hidden)]
+pub struct FnTask<F, R> {
+    name: &'static str,
+    function: TaskArgument<F>,
+    output: TypedOutput<R>,
+    /// Turns the result into what the untyped handle of the task reports.
+    report: fn(&R) -> MyResult<PythonTaskResult>,
+}
+
+impl<F, R> FnTask<F, R>
+where
+    F: FnOnce(Python) -> R + Send + 'static,
+    R: TaskReturn,
+{
+    pub fn new(name: &'static str, function: F) -> Self {
+        Self::with_report(name, function, R::to_task_result)
+    }
+}
+
+impl<F, R> FnTask<F, R>
+where
+    F: FnOnce(Python) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    pub fn with_report(
+        name: &'static str,
+        function: F,
+        report: fn(&R) -> MyResult<PythonTaskResult>,
+    ) -> Self {
+        Self {
+            name,
+            function: TaskArgument::new(function),
+            output: TypedOutput::new(),
+            report,
+        }
+    }
+
+    /// The output the caller waits on.
+    pub fn output(&self) -> TypedOutput<R> {
+        self.output.clone()
+    }
+}
+
+impl<F, R> PythonTask for FnTask<F, R>
+where
+    F: FnOnce(Python) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    fn execute(&self, py: Python) -> MyResult<PythonTaskResult> {
+        let result = (self.function.take())(py);
+        let status = (self.report)(&result);
+        self.output.complete_with(result, status)
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+/// Reports only whether a submitted closure succeeded, its value having no `PythonTaskResult`
+/// representation in general.
+pub(crate) fn report_completion<T>(result: &MyResult<T>) -> MyResult<PythonTaskResult> {
+    match result {
+        Ok(_) => Ok(PythonTaskResult::None),
+        Err(err) => Err(err.clone()),
+    }
+}
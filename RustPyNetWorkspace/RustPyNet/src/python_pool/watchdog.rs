@@ -0,0 +1,180 @@
+use std::os::raw::c_long;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use pyo3::{ffi, Python};
+
+use crate::python_pool::sync::Recover;
+
+/// Interrupts the task running on a pool worker once it exceeds its deadline.
+///
+/// The worker registers every task it starts with `begin` and clears it with `end`. A monitor
+/// thread sleeps until the deadline of the running task and, if the task is still running by
+/// then, raises a `TimeoutError` inside the worker thread through
+/// `PyThreadState_SetAsyncExc`. The exception is raised the next time the interpreter
+/// executes bytecode on the worker, so tasks stuck inside a blocking C call (e.g.
+/// `time.sleep`) are only interrupted once that call returns.
+///
+/// Cloning a watchdog yields a handle to the same state.
+#[derive(Clone)]
+pub(crate) struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+    /// The logging switch of the queue whose worker is watched.
+    logging: Arc<AtomicBool>,
+}
+
+struct State {
+    /// Python thread identifier of the worker, once it attached.
+    thread_id: Option<c_long>,
+    running: Option<RunningTask>,
+    next_id: u64,
+    stopped: bool,
+}
+
+struct RunningTask {
+    id: u64,
+    deadline: Instant,
+    interrupted: bool,
+}
+
+impl Watchdog {
+    pub(crate) fn new(logging: Arc<AtomicBool>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    thread_id: None,
+                    running: None,
+                    next_id: 0,
+                    stopped: false,
+                }),
+                changed: Condvar::new(),
+                logging,
+            }),
+        }
+    }
+
+    /// Records the calling thread as the worker whose tasks are watched.
+    ///
+    /// A worker restarted after a failure attaches again, forgetting the task its predecessor
+    /// was running.
+    pub(crate) fn attach(&self, py: Python) -> PyResult<()> {
+        let thread_id: u64 = py
+            .import("threading")?
+            .call_method0("get_ident")?
+            .extract()?;
+        let mut state = self.shared.state.lock().recover();
+        state.thread_id = Some(thread_id as c_long);
+        state.running = None;
+        Ok(())
+    }
+
+    /// Spawns the monitor thread, which runs until `stop` is called.
+    pub(crate) fn spawn(&self) -> std::io::Result<thread::JoinHandle<()>> {
+        let shared = self.shared.clone();
+        thread::Builder::new()
+            .name("rustpynet-watchdog".to_string())
+            .spawn(move || monitor(&shared))
+    }
+
+    /// Stops the monitor thread.
+    pub(crate) fn stop(&self) {
+        self.shared.state.lock().recover().stopped = true;
+        self.shared.changed.notify_all();
+    }
+
+    /// Marks the start of a task on the worker thread. Tasks without a deadline are never
+    /// interrupted.
+    ///
+    /// Must be called while holding the GIL.
+    pub(crate) fn begin(&self, _py: Python, deadline: Option<Instant>) {
+        let mut state = self.shared.state.lock().recover();
+        state.next_id += 1;
+        state.running = deadline.map(|deadline| RunningTask {
+            id: state.next_id,
+            deadline,
+            interrupted: false,
+        });
+        self.shared.changed.notify_all();
+    }
+
+    /// Marks the end of the task started with `begin`, returning whether it was interrupted.
+    ///
+    /// Must be called while holding the GIL, so the monitor cannot interrupt the worker between
+    /// two tasks. An interruption that was requested but not raised yet is discarded.
+    pub(crate) fn end(&self, _py: Python) -> bool {
+        let mut state = self.shared.state.lock().recover();
+        let interrupted = state
+            .running
+            .take()
+            .is_some_and(|running| running.interrupted);
+
+        if interrupted {
+            if let Some(thread_id) = state.thread_id {
+                // Passing a null exception clears a pending asynchronous exception.
+                unsafe {
+                    ffi::PyThreadState_SetAsyncExc(thread_id, std::ptr::null_mut());
+                }
+            }
+        }
+        interrupted
+    }
+}
+
+/// Body of the monitor thread.
+fn monitor(shared: &Shared) {
+    let mut state = shared.state.lock().recover();
+    loop {
+        if state.stopped {
+            return;
+        }
+
+        let (id, deadline) = match &state.running {
+            Some(running) if !running.interrupted => (running.id, running.deadline),
+            _ => {
+                state = shared.changed.wait(state).recover();
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        if now < deadline {
+            state = shared
+                .changed
+                .wait_timeout(state, deadline - now)
+                .recover()
+                .0;
+            continue;
+        }
+
+        // The state lock is released before taking the GIL, since the worker takes the GIL
+        // first. Holding the GIL guarantees the worker is not between `begin`
+        // and `end` bookkeeping, so the task checked below is the one actually running.
+        drop(state);
+        Python::with_gil(|_py| {
+            let mut state = shared.state.lock().recover();
+            let thread_id = state.thread_id;
+            if let (Some(thread_id), Some(running)) = (thread_id, state.running.as_mut()) {
+                if running.id == id && !running.interrupted {
+                    log!(
+                        shared.logging.load(Ordering::Relaxed),
+                        warn,
+                        "Interrupting a Python task that exceeded its deadline."
+                    );
+                    unsafe {
+                        ffi::PyThreadState_SetAsyncExc(thread_id, ffi::PyExc_TimeoutError);
+                    }
+                    running.interrupted = true;
+                }
+            }
+        });
+        state = shared.state.lock().recover();
+    }
+}
@@ -0,0 +1,7 @@
+//! Checks the compile errors `run_with_py` reports for invalid functions.
+
+/// With `explicit_py`, a body using `py` without declaring it doesn't compile.
+#[test]
+fn undeclared_py_is_rejected() {
+    trybuild::TestCases::new().compile_fail("tests/ui/undeclared_py.rs");
+}
@@ -0,0 +1,8 @@
+use RustPyNet::run_with_py;
+
+#[run_with_py(explicit_py)]
+fn compute_sum() -> Result<i32, RustPyNet::python_pool::pool::PythonTaskError> {
+    Ok(py.eval("1 + 2", None, None)?.extract()?)
+}
+
+fn main() {}
@@ -4,18 +4,414 @@ extern crate proc_macro;
 
 use heck::CamelCase;
 use proc_macro::TokenStream;
-use pyo3::{PyErr, PyObject, PyResult, Python};
+use proc_macro2::Span;
 use quote::quote;
-use syn::ExprPath;
-use syn::Ident;
-use syn::{parse_macro_input, ItemFn, ReturnType};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, FnArg, GenericParam, Generics, Ident, ItemFn, LitInt, LitStr,
+    Pat, ReturnType, Signature, Token, Type, WherePredicate,
+};
 extern crate quote;
 use quote::format_ident;
 
+/// Arguments accepted by `#[run_with_py(...)]`.
+#[derive(Default)]
+struct RunWithPyArgs {
+    /// `async`: generate an `async fn` that awaits the result instead of blocking.
+    asynchronous: bool,
+    /// `timeout_ms = <u64>`: default timeout applied to every call.
+    timeout_ms: Option<LitInt>,
+    /// `priority = low | normal | high`: priority every call is enqueued with.
+    priority: Option<Ident>,
+    /// `pool = "<name>"`: named queue the calls are enqueued into, instead of the global one.
+    pool: Option<LitStr>,
+    /// `explicit_py`: don't give the body an implicit `py`, it must declare a `Python` parameter
+    /// to use the interpreter.
+    explicit_py: bool,
+}
+
+impl Parse for RunWithPyArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = RunWithPyArgs::default();
+        while !input.is_empty() {
+            if input.peek(Token![async]) {
+                input.parse::<Token![async]>()?;
+                args.asynchronous = true;
+            } else {
+                let key: Ident = input.parse()?;
+                if key == "explicit_py" {
+                    args.explicit_py = true;
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                    continue;
+                }
+                input.parse::<Token![=]>()?;
+                match key.to_string().as_str() {
+                    "timeout_ms" => {
+                        let value: LitInt = input.parse()?;
+                        value.base10_parse::<u64>()?;
+                        args.timeout_ms = Some(value);
+                    }
+                    "priority" => {
+                        let value: Ident = input.parse()?;
+                        let variant = match value.to_string().as_str() {
+                            "low" => "Low",
+                            "normal" => "Normal",
+                            "high" => "High",
+                            _ => {
+                                return Err(syn::Error::new(
+                                    value.span(),
+                                    "unknown priority, expected `low`, `normal` or `high`",
+                                ))
+                            }
+                        };
+                        args.priority = Some(Ident::new(variant, value.span()));
+                    }
+                    "pool" => args.pool = Some(input.parse()?),
+                    _ => return Err(syn::Error::new(
+                        key.span(),
+                        "unknown argument, expected `async`, `timeout_ms`, `priority`, `pool` or `explicit_py`",
+                    )),
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// A parameter of the function given to `#[run_with_py]`, stored in a field of the task.
+struct TaskParam {
+    /// Name of the parameter, and of the field holding it.
+    ident: Ident,
+    mutability: Option<Token![mut]>,
+    /// Type of the parameter in the generated functions.
+    decl_ty: Type,
+    /// Type of the parameter in the function body, borrowed parameters losing their lifetime.
+    ty: Type,
+    /// The referenced type when the parameter is borrowed, the task then holding an owned copy.
+    borrowed: Option<Type>,
+    /// Whether the parameter is the `context: PythonTaskContext` every function used to take,
+    /// which is allowed to go unused.
+    legacy_context: bool,
+}
+
+impl TaskParam {
+    fn parse(arg: &FnArg) -> syn::Result<Self> {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new(
+                    receiver.span(),
+                    "`self` must be the first parameter",
+                ))
+            }
+        };
+        let pat = match &*arg.pat {
+            Pat::Ident(pat) if pat.ident == "self" => {
+                return Err(syn::Error::new(
+                    arg.ty.span(),
+                    "typed receivers aren't supported, take `&self` or `self`",
+                ))
+            }
+            Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat,
+            pat => {
+                return Err(syn::Error::new(
+                    pat.span(),
+                    "expected a named parameter, such as `user_id: i64`",
+                ))
+            }
+        };
+
+        let legacy_context = is_python_task_context(&arg.ty);
+        let borrowed = match &*arg.ty {
+            Type::Reference(reference) if reference.mutability.is_some() => {
+                return Err(syn::Error::new(
+                    reference.span(),
+                    "mutable references can't be sent to the Python pool",
+                ))
+            }
+            Type::Reference(reference) => Some((*reference.elem).clone()),
+            Type::ImplTrait(ty) => {
+                return Err(syn::Error::new(
+                    ty.span(),
+                    "`impl Trait` parameters aren't supported, declare a generic type parameter instead",
+                ))
+            }
+            // A context declared by value is taken by reference, as it always has been.
+            ty if legacy_context => Some(ty.clone()),
+            _ => None,
+        };
+        let ty: Type = match &borrowed {
+            // The task holds an owned copy, so the lifetime of the caller's borrow is dropped.
+            Some(elem) => syn::parse_quote! { &#elem },
+            None => (*arg.ty).clone(),
+        };
+        let decl_ty = if legacy_context {
+            ty.clone()
+        } else {
+            (*arg.ty).clone()
+        };
+
+        Ok(Self {
+            ident: pat.ident.clone(),
+            mutability: pat.mutability,
+            decl_ty,
+            ty,
+            borrowed,
+            legacy_context,
+        })
+    }
+
+    /// Type of the field holding the parameter in the task.
+    fn field_type(&self) -> proc_macro2::TokenStream {
+        match &self.borrowed {
+            Some(elem) => quote! { <#elem as ::std::borrow::ToOwned>::Owned },
+            None => {
+                let ty = &self.ty;
+                quote! { ::RustPyNet::__private::TaskArgument<#ty> }
+            }
+        }
+    }
+
+    /// Type of the value the task is created with.
+    fn owned_type(&self) -> proc_macro2::TokenStream {
+        match &self.borrowed {
+            Some(elem) => quote! { <#elem as ::std::borrow::ToOwned>::Owned },
+            None => {
+                let ty = &self.ty;
+                quote! { #ty }
+            }
+        }
+    }
+
+    /// Converts the parameter received by the generated functions into the owned value.
+    fn owned_value(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        match &self.borrowed {
+            Some(_) => quote! { ::std::borrow::ToOwned::to_owned(#ident) },
+            None => quote! { #ident },
+        }
+    }
+
+    /// Initializes the field from the owned value.
+    fn init_field(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        match &self.borrowed {
+            Some(_) => quote! { #ident },
+            None => quote! { ::RustPyNet::__private::TaskArgument::new(#ident) },
+        }
+    }
+
+    /// Reads the field to pass it to the function body.
+    fn read_field(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        match &self.borrowed {
+            Some(_) => quote! { ::std::borrow::Borrow::borrow(&self.#ident) },
+            None => quote! { self.#ident.take() },
+        }
+    }
+}
+
+/// Rejects the signatures the generated functions can't reproduce.
+fn check_signature(sig: &Signature) -> syn::Result<()> {
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new(
+            asyncness.span(),
+            "the function body runs on the pool and can't be `async`, use `#[run_with_py(async)]` to make the generated functions async",
+        ));
+    }
+    if let Some(constness) = &sig.constness {
+        return Err(syn::Error::new(
+            constness.span(),
+            "`run_with_py` functions can't be `const`",
+        ));
+    }
+    if let Some(unsafety) = &sig.unsafety {
+        return Err(syn::Error::new(
+            unsafety.span(),
+            "`run_with_py` functions can't be `unsafe`",
+        ));
+    }
+    if let Some(abi) = &sig.abi {
+        return Err(syn::Error::new(
+            abi.span(),
+            "`run_with_py` functions can't have an ABI",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new(
+            variadic.span(),
+            "`run_with_py` functions can't be variadic",
+        ));
+    }
+    for param in &sig.generics.params {
+        if let GenericParam::Const(param) = param {
+            return Err(syn::Error::new(
+                param.span(),
+                "const generic parameters aren't supported",
+            ));
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if let Type::ImplTrait(ty) = &**ty {
+            return Err(syn::Error::new(
+                ty.span(),
+                "`impl Trait` return types aren't supported, the result must be a `Result`",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Outer attributes of the function, sorted by the generated items they are copied to.
+#[derive(Default)]
+struct FnAttrs {
+    /// `cfg`, `cfg_attr` and lint attributes, copied to every generated item.
+    everywhere: Vec<Attribute>,
+    /// Doc comments, kept on the function itself.
+    docs: Vec<Attribute>,
+    /// Other attributes, such as `deprecated` or `must_use`, copied to every generated function.
+    functions: Vec<Attribute>,
+}
+
+impl FnAttrs {
+    fn sort(attrs: &[Attribute]) -> Self {
+        let mut sorted = FnAttrs::default();
+        for attr in attrs {
+            let name = attr
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            match name.as_str() {
+                "cfg" | "cfg_attr" | "allow" | "warn" | "deny" | "forbid" | "expect" => {
+                    sorted.everywhere.push(attr.clone())
+                }
+                "doc" => sorted.docs.push(attr.clone()),
+                _ => sorted.functions.push(attr.clone()),
+            }
+        }
+        sorted
+    }
+}
+
+/// Bounds the type parameters of the function by `Send + 'static`, for the task holding them to
+/// be sent to the pool. Lifetime parameters only apply to the borrowed parameters of the generated
+/// functions, so the task struct drops them.
+fn with_task_bounds(generics: &Generics, keep_lifetimes: bool) -> Generics {
+    let mut bounded = generics.clone();
+    bounded.params = generics
+        .params
+        .iter()
+        .filter(|param| keep_lifetimes || matches!(param, GenericParam::Type(_)))
+        .cloned()
+        .collect();
+    if bounded.params.is_empty() {
+        bounded.lt_token = None;
+        bounded.gt_token = None;
+    }
+
+    let task_bounds: [syn::TypeParamBound; 2] = [
+        syn::parse_quote! { ::std::marker::Send },
+        syn::parse_quote! { 'static },
+    ];
+    let idents: Vec<Ident> = bounded
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    for ident in idents {
+        // The bounds go where the function declares the others, as clippy expects.
+        let predicate = bounded.where_clause.as_mut().and_then(|where_clause| {
+            where_clause
+                .predicates
+                .iter_mut()
+                .find_map(|predicate| match predicate {
+                    WherePredicate::Type(predicate) if is_ident(&predicate.bounded_ty, &ident) => {
+                        Some(predicate)
+                    }
+                    _ => None,
+                })
+        });
+        match predicate {
+            Some(predicate) => predicate.bounds.extend(task_bounds.iter().cloned()),
+            None => {
+                if let Some(param) = bounded.type_params_mut().find(|param| param.ident == ident) {
+                    param.bounds.extend(task_bounds.iter().cloned());
+                }
+            }
+        }
+    }
+    bounded
+}
+
+/// The `py: Python` parameter of a function, through which the body receives the GIL token of the
+/// worker instead of the implicit `py`.
+struct PyParam {
+    ident: Ident,
+    mutability: Option<Token![mut]>,
+    ty: Type,
+}
+
+impl PyParam {
+    fn parse(arg: &FnArg) -> Option<Self> {
+        let arg = match arg {
+            FnArg::Typed(arg) if is_python(&arg.ty) => arg,
+            _ => return None,
+        };
+        match &*arg.pat {
+            Pat::Ident(pat) => Some(Self {
+                ident: pat.ident.clone(),
+                mutability: pat.mutability,
+                ty: (*arg.ty).clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn is_ident(ty: &Type, ident: &Ident) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident(ident),
+        _ => false,
+    }
+}
+
+fn is_python(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => {
+            path.qself.is_none()
+                && path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Python")
+        }
+        _ => false,
+    }
+}
+
+fn is_python_task_context(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => {
+            path.qself.is_none()
+                && path.path.segments.last().is_some_and(|segment| {
+                    segment.ident == "PythonTaskContext" && segment.arguments.is_empty()
+                })
+        }
+        _ => false,
+    }
+}
+
 /// The `run_with_py` procedural macro facilitates the execution of a given function within a Python context.
 ///
 /// It dynamically creates a struct and its implementation based on the provided function. The function is then executed
-/// within a Python context, and its results are passed back through a channel.
+/// within a Python context, and its result is handed back to the caller by the pool.
 ///
 /// # Usage
 ///
@@ -28,76 +424,556 @@ use quote::format_ident;
 ///
 /// This macro will create the necessary infrastructure for the function to be run in a Python context.
 ///
+/// # Async
+///
+/// With `#[run_with_py(async)]` the generated function is an `async fn` that awaits the result
+/// instead of blocking the calling thread. The returned future works with any executor. When
+/// the queue is full and its overflow policy is `OverflowPolicy::Block`, the future also waits
+/// for room without blocking the thread, see `PythonTaskQueue::enqueue_async`.
+///
+/// ```ignore
+/// #[run_with_py(async)]
+/// fn your_function_name(context: &PythonTaskContext) -> YourReturnType {
+///     // Your function implementation here
+/// }
+///
+/// let result = your_function_name(&context).await;
+/// ```
+///
+/// # Timeouts
+///
+/// A second function named `<name>_with_timeout` is generated, taking the timeout as an extra
+/// `std::time::Duration` parameter. If the result is not available in time the call returns
+/// `PythonTaskError::Timeout`, and the task is skipped if the pool has not started it yet.
+/// `#[run_with_py(timeout_ms = 500)]` applies a default timeout to the plain function.
+///
+/// ```ignore
+/// #[run_with_py(timeout_ms = 500)]
+/// fn your_function_name(context: &PythonTaskContext) -> YourReturnType {
+///     // Your function implementation here
+/// }
+///
+/// let result = your_function_name_with_timeout(&context, Duration::from_secs(2));
+/// ```
+///
+/// Async functions can be combined with a timeout as well: the future resolves with
+/// `PythonTaskError::Timeout` once the timeout elapses, even if no pool processes the queue, and
+/// the task is skipped if the pool has not started it yet.
+///
+/// # Priorities
+///
+/// `#[run_with_py(priority = high)]` enqueues every call with the given priority, one of `low`,
+/// `normal` (the default) or `high`. The pool always runs higher priority tasks first.
+///
+/// A third function named `<name>_with_options` takes a `TaskOptions` to choose the priority and
+/// timeout of a single call, in place of the ones given to the attribute.
+///
+/// ```ignore
+/// let options = TaskOptions::new().priority(TaskPriority::Low);
+/// let result = your_function_name_with_options(&context, options);
+/// ```
+///
+/// # Pools
+///
+/// Calls are enqueued into the global `CLIENT_PYTHON_PROCESS_QUEUE` unless the attribute names
+/// another queue with `#[run_with_py(pool = "analytics")]`, which is then processed by the pool
+/// started with `PythonPool::start_named("analytics")`.
+///
+/// A function named `<name>_on` takes the queue to use as its first parameter, to choose the pool
+/// at runtime.
+///
+/// ```ignore
+/// let queue = PythonTaskQueue::named("reports");
+/// let result = your_function_name_on(&queue, &context, TaskOptions::new());
+/// ```
+///
 /// # Parameters
 ///
-/// - `dict`: A `HashMap` containing data that you wish to pass to the Python context.
+/// The function can take any number of named parameters, each stored in a field of the generated
+/// task, so their types must be `Send + 'static`. Borrowed parameters such as `&str` are stored as
+/// their `ToOwned` counterpart, and the body receives a reference to it. A parameter declared as
+/// `PythonTaskContext` is taken by reference by the generated functions.
+///
+/// ```ignore
+/// #[run_with_py]
+/// fn score(py: Python, user_id: i64, weights: Vec<f64>) -> Result<f64, PyErr> {
+///     // Your function implementation here
+/// }
+///
+/// let score = score(42, vec![0.5, 1.5]);
+/// ```
+///
+/// A parameter of type `Python`, whatever its name, receives the GIL token of the pool worker and
+/// is left out of the generated functions. Without it the body can use an implicit `py`, unless
+/// the function is declared with `#[run_with_py(explicit_py)]`.
+///
+/// # Methods
+///
+/// On an inherent method taking `&self` or `self`, the task captures a clone of the receiver, or
+/// the receiver itself, and the generated functions are methods as well. The body moves to a
+/// hidden method, and no task struct is generated. `&mut self` is rejected.
+///
+/// ```ignore
+/// impl Analyzer {
+///     #[run_with_py]
+///     fn classify(&self, py: Python, text: &str) -> Result<String, PyErr> {
+///         // Your method implementation here
+///     }
+/// }
+///
+/// let label = analyzer.classify(&text);
+/// ```
+///
+/// # Visibility, attributes and generics
+///
+/// The generated functions and task struct have the visibility of the function. Its doc comments
+/// stay on the function, `cfg` and lint attributes are copied to every generated item, and the
+/// other attributes to every generated function. Type parameters get a `Send + 'static` bound.
+/// `async`, `const` and `unsafe` functions, ABIs, const generics, and `impl Trait` parameters or
+/// return types are rejected.
 ///
 /// # Returns
 ///
-/// Returns whatever your function is intended to return, wrapped in the necessary channel and context management code.
+/// Returns whatever your function is intended to return, wrapped in the necessary queueing and context management code.
+///
+/// The return type can be any `Result<T, E>` implementing `TaskReturn`: `T` is `Send + 'static`
+/// and `E` implements `From<PythonTaskError>`, through which failures of the pool such as a
+/// timeout are returned. `?` on Python calls additionally requires `E: From<PyErr>`.
+///
+/// ```ignore
+/// #[run_with_py]
+/// fn word_lengths(py: Python) -> Result<Vec<usize>, PyErr> {
+///     py.eval("[len(word) for word in 'the quick fox'.split()]", None, None)?.extract()
+/// }
+/// ```
 ///
 /// # Errors
 ///
 /// If there are any issues with obtaining the Python context or executing the function, an error will be returned.
 #[proc_macro_attribute]
 pub fn run_with_py(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as RunWithPyArgs);
     let input = parse_macro_input!(item as ItemFn);
+    if let Err(err) = check_signature(&input.sig) {
+        return err.to_compile_error().into();
+    }
     let name = &input.sig.ident;
+    let vis = &input.vis;
     let block = &input.block;
+    let attrs = FnAttrs::sort(&input.attrs);
+    let everywhere = &attrs.everywhere;
+    let docs = &attrs.docs;
+    let fn_attrs = &attrs.functions;
     let ret_type = match &input.sig.output {
         ReturnType::Default => quote! { () },
         ReturnType::Type(_, ty) => quote! { #ty },
     };
 
-    let task_struct_name = format_ident!("{}Task", name.to_string().to_camel_case());
+    // Methods send a clone of their receiver to the pool, or the receiver itself when they take
+    // it by value.
+    let receiver = match input.sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.mutability.is_some() => {
+            return syn::Error::new(
+                receiver.span(),
+                "methods taking `&mut self` can't run on the pool, which works on a clone of the receiver",
+            )
+            .to_compile_error()
+            .into()
+        }
+        Some(FnArg::Receiver(receiver)) => Some(receiver),
+        _ => None,
+    };
 
-    let expanded = quote! {
-        struct #task_struct_name {
-            context: PythonTaskContext,
+    let mut py_param: Option<PyParam> = None;
+    let mut params = Vec::new();
+    for arg in input.sig.inputs.iter().skip(receiver.iter().count()) {
+        match PyParam::parse(arg) {
+            Some(_) if py_param.is_some() => {
+                return syn::Error::new(arg.span(), "only one `Python` parameter can be declared")
+                    .to_compile_error()
+                    .into()
+            }
+            Some(param) => py_param = Some(param),
+            None => match TaskParam::parse(arg) {
+                Ok(param) => params.push(param),
+                Err(err) => return err.to_compile_error().into(),
+            },
         }
+    }
 
-        impl PythonTask for #task_struct_name {
-            fn execute(&self, py: Python, tx: Sender<MyResult<PythonTaskResult>>) -> MyResult<PythonTaskResult> {
-                let result: PyResult<PythonTaskResult> = (|context: &PythonTaskContext| {
-                    #block
-                })(&self.context);
-
-                // Send the result back through the provided channel.
-                let send_result = match result {
-                    Ok(val) => tx.send(Ok(val)),
-                    Err(err) => tx.send(Err(PythonTaskError::PythonError(format!("{:?}", err)))),
-                };
-
-                // Check if sending was successful.
-                match send_result {
-                    Ok(_) => Ok(PythonTaskResult::None),
-                    Err(_) => Err(PythonTaskError::OtherError("Failed to send result back.".to_string())),
+    let param_idents: Vec<_> = params.iter().map(|param| &param.ident).collect();
+    let param_decls: Vec<_> = params
+        .iter()
+        .map(|param| {
+            let ident = &param.ident;
+            let ty = &param.decl_ty;
+            quote! { #ident: #ty }
+        })
+        .collect();
+    let mut closure_params: Vec<_> = params
+        .iter()
+        .map(|param| {
+            let mutability = &param.mutability;
+            let ident = &param.ident;
+            let ty = &param.ty;
+            let allow = if param.legacy_context {
+                quote! { #[allow(unused_variables)] }
+            } else {
+                quote! {}
+            };
+            quote! { #allow #mutability #ident: #ty }
+        })
+        .collect();
+    let field_types: Vec<_> = params.iter().map(TaskParam::field_type).collect();
+    let owned_types: Vec<_> = params.iter().map(TaskParam::owned_type).collect();
+    let init_fields: Vec<_> = params.iter().map(TaskParam::init_field).collect();
+    let owned_values: Vec<_> = params.iter().map(TaskParam::owned_value).collect();
+    let mut field_reads: Vec<_> = params.iter().map(TaskParam::read_field).collect();
+
+    // Names the expansion introduces, resolved at the macro definition so that they can't clash
+    // with the parameters of the function or be seen by its body.
+    let internal = |name: &str| Ident::new(name, Span::mixed_site());
+    let result = internal("result");
+    let python_queue = internal("python_queue");
+    let named_queue = internal("NAMED_QUEUE");
+    let options = internal("options");
+    let timeout = internal("timeout");
+    let task = internal("task");
+    let output = internal("output");
+    let rx = internal("rx");
+    // Field names aren't hygienic, so the one holding the output can't be named after a common
+    // parameter name.
+    let output_field = format_ident!("__output");
+
+    // A declared `Python` parameter receives the GIL token of the worker, which otherwise is
+    // visible to the body as `py`, unless `explicit_py` hides it.
+    let worker_py = match &py_param {
+        Some(param) => {
+            let mutability = &param.mutability;
+            let ident = &param.ident;
+            let worker_py = internal("py");
+            let ty = &param.ty;
+            closure_params.insert(0, quote! { #mutability #ident: #ty });
+            field_reads.insert(0, quote! { #worker_py });
+            worker_py
+        }
+        None if args.explicit_py => internal("_py"),
+        None => format_ident!("py"),
+    };
+
+    let task_struct_name = format_ident!("{}Task", name.to_string().to_camel_case());
+
+    let fn_generics = with_task_bounds(&input.sig.generics, true);
+    let fn_where = &fn_generics.where_clause;
+    let task_generics = with_task_bounds(&input.sig.generics, false);
+    let (impl_generics, ty_generics, where_clause) = task_generics.split_for_impl();
+    let type_params: Vec<_> = task_generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    // Type parameters only used by the return type still have to appear in a field.
+    let marker_field = format_ident!("__marker");
+    let (marker_decl, marker_init) = if type_params.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! { #marker_field: ::std::marker::PhantomData<fn() -> (#(#type_params,)*)>, },
+            quote! { #marker_field: ::std::marker::PhantomData, },
+        )
+    };
+
+    // Methods are linked to through `Self`.
+    let link = match receiver {
+        Some(_) => format!("Self::{}", name),
+        None => name.to_string(),
+    };
+    let struct_doc = format!(
+        "The task executing [`{}`] on a Python pool, to enqueue it by hand.",
+        name
+    );
+    let new_doc = format!(
+        "Creates the task from the arguments of [`{}`], borrowed ones being owned.",
+        name
+    );
+    let with_timeout_doc = format!(
+        "Calls [`{}`], returning `PythonTaskError::Timeout` if the result isn't available within `timeout`.",
+        link
+    );
+    let with_options_doc = format!(
+        "Calls [`{}`] with the priority and timeout of `options`.",
+        link
+    );
+    let on_doc = format!(
+        "Calls [`{}`] through the given queue, with the priority and timeout of `options`.",
+        link
+    );
+
+    let with_timeout_name = format_ident!("{}_with_timeout", name);
+    let with_options_name = format_ident!("{}_with_options", name);
+    let on_name = format_ident!("{}_on", name);
+
+    let asyncness = if args.asynchronous {
+        quote! { async }
+    } else {
+        quote! {}
+    };
+
+    let wait_for_result = if args.asynchronous {
+        quote! {
+            match #options.timeout {
+                ::std::option::Option::Some(#timeout) => #rx.with_timeout(#timeout).await,
+                ::std::option::Option::None => #rx.await,
+            }
+        }
+    } else {
+        quote! {
+            match #options.timeout {
+                ::std::option::Option::Some(#timeout) => {
+                    ::RustPyNet::__private::PythonTaskQueue::wait_for_result_timeout(#rx, #timeout)
                 }
+                ::std::option::Option::None => ::RustPyNet::__private::PythonTaskQueue::wait_for_result(#rx),
             }
         }
+    };
+
+    // Async functions wait for room in a full queue without blocking the executor thread.
+    let enqueue = if args.asynchronous {
+        quote! {
+            #python_queue.enqueue_async(::std::boxed::Box::new(#task), #options).await
+        }
+    } else {
+        quote! {
+            #python_queue.enqueue_with_options(::std::boxed::Box::new(#task), #options)
+        }
+    };
+
+    let dot_await = if args.asynchronous {
+        quote! { .await }
+    } else {
+        quote! {}
+    };
 
-        fn #name(context: &PythonTaskContext) -> #ret_type {
-            let task = #task_struct_name {
-                context: context.clone(),
+    let mut default_options = quote! { ::RustPyNet::__private::TaskOptions::new() };
+    if let Some(timeout_ms) = &args.timeout_ms {
+        default_options = quote! {
+            #default_options.timeout(::std::time::Duration::from_millis(#timeout_ms))
+        };
+    }
+    if let Some(priority) = &args.priority {
+        default_options = quote! {
+            #default_options.priority(::RustPyNet::__private::TaskPriority::#priority)
+        };
+    }
+
+    let default_queue = match &args.pool {
+        // The named queue is only looked up on the first call, so that calls don't contend on
+        // the registry of named queues.
+        Some(pool) => quote! {
+            let #python_queue = {
+                static #named_queue: ::std::sync::OnceLock<::RustPyNet::__private::PythonTaskQueue> =
+                    ::std::sync::OnceLock::new();
+                ::std::clone::Clone::clone(
+                    #named_queue
+                        .get_or_init(|| ::RustPyNet::__private::PythonTaskQueue::named(#pool)),
+                )
             };
+        },
+        None => quote! {
+            let #python_queue = ::std::clone::Clone::clone(
+                &*::RustPyNet::__private::CLIENT_PYTHON_PROCESS_QUEUE,
+            );
+        },
+    };
+
+    // Methods can only expand to items of their `impl` block, so the body moves to a hidden
+    // method, and the task is a closure calling it on the receiver.
+    if let Some(receiver) = receiver {
+        let body_name = format_ident!("__run_with_py_{}", name);
+        let receiver_value = internal("receiver");
+        let (wrapper_receiver, take_receiver) = match receiver.reference {
+            Some(_) => (
+                quote! { &self },
+                quote! { ::std::clone::Clone::clone(self) },
+            ),
+            None => (quote! { self }, quote! { self }),
+        };
+        let mut body_params = closure_params;
+        if py_param.is_none() {
+            body_params.insert(
+                0,
+                quote! { #[allow(unused_variables)] #worker_py: ::RustPyNet::__private::Python },
+            );
+        }
+        let body_args: Vec<_> = params
+            .iter()
+            .map(|param| {
+                let ident = &param.ident;
+                match &param.borrowed {
+                    Some(_) => quote! { ::std::borrow::Borrow::borrow(&#ident) },
+                    None => quote! { #ident },
+                }
+            })
+            .collect();
+        let body_generics = &input.sig.generics;
+        let body_where = &input.sig.generics.where_clause;
 
-            let rx: std::sync::mpsc::Receiver<MyResult<PythonTaskResult>>;
+        let expanded = quote! {
+            #(#everywhere)*
+            #[doc(hidden)]
+            #[allow(clippy::too_many_arguments)]
+            fn #body_name #body_generics (#receiver, #(#body_params),*) -> #ret_type #body_where
+            #block
 
-            loop {
-                match RustPyNet::CLIENT_PYTHON_PROCESS_QUEUE.lock() {
-                    Ok(mut python_queue) => {
-                        rx = python_queue.enqueue(Box::new(task));
-                        break;
-                    }
-                    Err(_) => {
-                        let sleep_duration = std::time::Duration::from_millis(rand::random::<u64>() % 1000);
-                        println!("Not being able to lock on Pool!");
-                        std::thread::sleep(sleep_duration);
-                    }
+            #(#everywhere)*
+            #(#docs)*
+            #(#fn_attrs)*
+            #vis #asyncness fn #name #fn_generics (#wrapper_receiver, #(#param_decls),*) -> #ret_type #fn_where {
+                self.#with_options_name(#(#param_idents,)* #default_options)#dot_await
+            }
+
+            #(#everywhere)*
+            #[doc = #with_timeout_doc]
+            #(#fn_attrs)*
+            #[allow(dead_code, clippy::too_many_arguments)]
+            #vis #asyncness fn #with_timeout_name #fn_generics (
+                #wrapper_receiver,
+                #(#param_decls,)*
+                #timeout: ::std::time::Duration,
+            ) -> #ret_type #fn_where {
+                self.#with_options_name(#(#param_idents,)* #default_options.timeout(#timeout))#dot_await
+            }
+
+            #(#everywhere)*
+            #[doc = #with_options_doc]
+            #(#fn_attrs)*
+            #[allow(dead_code, clippy::too_many_arguments)]
+            #vis #asyncness fn #with_options_name #fn_generics (
+                #wrapper_receiver,
+                #(#param_decls,)*
+                #options: ::RustPyNet::__private::TaskOptions,
+            ) -> #ret_type #fn_where {
+                #default_queue
+
+                self.#on_name(&#python_queue, #(#param_idents,)* #options)#dot_await
+            }
+
+            #(#everywhere)*
+            #[doc = #on_doc]
+            #(#fn_attrs)*
+            #[allow(dead_code, clippy::too_many_arguments)]
+            #vis #asyncness fn #on_name #fn_generics (
+                #wrapper_receiver,
+                #python_queue: &::RustPyNet::__private::PythonTaskQueue,
+                #(#param_decls,)*
+                #options: ::RustPyNet::__private::TaskOptions,
+            ) -> #ret_type #fn_where {
+                let #receiver_value = #take_receiver;
+                #(let #param_idents = #owned_values;)*
+                let #task = ::RustPyNet::__private::FnTask::new(
+                    ::std::stringify!(#name),
+                    move |#worker_py: ::RustPyNet::__private::Python| {
+                        #receiver_value.#body_name(#worker_py, #(#body_args),*)
+                    },
+                );
+                let #output = #task.output();
+
+                let #rx = #enqueue;
+
+                #output.finish(#wait_for_result)
+            }
+        };
+        return TokenStream::from(expanded);
+    }
+
+    let expanded = quote! {
+        #(#everywhere)*
+        #[doc = #struct_doc]
+        #vis struct #task_struct_name #impl_generics #where_clause {
+            #(#param_idents: #field_types,)*
+            #output_field: ::RustPyNet::__private::TypedOutput<#ret_type>,
+            #marker_decl
+        }
+
+        #(#everywhere)*
+        impl #impl_generics #task_struct_name #ty_generics #where_clause {
+            #[doc = #new_doc]
+            #[allow(dead_code, clippy::too_many_arguments)]
+            #vis fn new(#(#param_idents: #owned_types),*) -> Self {
+                Self {
+                    #(#param_idents: #init_fields,)*
+                    #output_field: ::RustPyNet::__private::TypedOutput::new(),
+                    #marker_init
                 }
             }
+        }
+
+        #(#everywhere)*
+        impl #impl_generics ::RustPyNet::__private::PythonTask for #task_struct_name #ty_generics #where_clause {
+            fn execute(
+                &self,
+                #worker_py: ::RustPyNet::__private::Python,
+            ) -> ::RustPyNet::__private::MyResult<::RustPyNet::__private::PythonTaskResult> {
+                // Python exceptions propagated with `?` are converted through the `From<PyErr>`
+                // implementation of the declared error type.
+                let #result: #ret_type = (|#(#closure_params),*| {
+                    #block
+                })(#(#field_reads),*);
+
+                // Hand the typed result over to the caller.
+                self.#output_field.complete(#result)
+            }
+
+            fn name(&self) -> &str {
+                ::std::stringify!(#name)
+            }
+        }
+
+        #(#everywhere)*
+        #(#docs)*
+        #(#fn_attrs)*
+        #vis #asyncness fn #name #fn_generics (#(#param_decls),*) -> #ret_type #fn_where {
+            #with_options_name(#(#param_idents,)* #default_options)#dot_await
+        }
+
+        #(#everywhere)*
+        #[doc = #with_timeout_doc]
+        #(#fn_attrs)*
+        #[allow(dead_code, clippy::too_many_arguments)]
+        #vis #asyncness fn #with_timeout_name #fn_generics (
+            #(#param_decls,)*
+            #timeout: ::std::time::Duration,
+        ) -> #ret_type #fn_where {
+            #with_options_name(#(#param_idents,)* #default_options.timeout(#timeout))#dot_await
+        }
+
+        #(#everywhere)*
+        #[doc = #with_options_doc]
+        #(#fn_attrs)*
+        #[allow(dead_code, clippy::too_many_arguments)]
+        #vis #asyncness fn #with_options_name #fn_generics (
+            #(#param_decls,)*
+            #options: ::RustPyNet::__private::TaskOptions,
+        ) -> #ret_type #fn_where {
+            #default_queue
+
+            #on_name(&#python_queue, #(#param_idents,)* #options)#dot_await
+        }
+
+        #(#everywhere)*
+        #[doc = #on_doc]
+        #(#fn_attrs)*
+        #[allow(dead_code, clippy::too_many_arguments)]
+        #vis #asyncness fn #on_name #fn_generics (
+            #python_queue: &::RustPyNet::__private::PythonTaskQueue,
+            #(#param_decls,)*
+            #options: ::RustPyNet::__private::TaskOptions,
+        ) -> #ret_type #fn_where {
+            let #task = #task_struct_name::new(#(#owned_values),*);
+            let #output = #task.#output_field.clone();
+
+            let #rx = #enqueue;
 
-            PythonTaskQueue::wait_for_result(rx)
+            #output.finish(#wait_for_result)
         }
     };
 
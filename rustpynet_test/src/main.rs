@@ -1,10 +1,9 @@
 use RustPyNet::python_pool::pool::PythonTaskError;
-use RustPyNet::python_pool::pool::PythonTaskQueue;
-use RustPyNet::python_pool::pool::{start_processing_host_python_tasks, PythonTaskResult};
+use RustPyNet::python_pool::pool::{PythonPool, PythonTaskResult, ShutdownMode};
 use RustPyNet::run_with_py;
 
+use pyo3::PyResult;
 use pyo3::ToPyObject;
-use pyo3::{PyResult, Python};
 use std::collections::HashMap;
 
 use pyo3::types::IntoPyDict;
@@ -12,8 +11,7 @@ use pyo3::types::PyDict;
 use pyo3::Py;
 use pyo3::PyAny;
 use pyo3::PyObject;
-use std::sync::mpsc::Sender;
-use RustPyNet::python_pool::pool::{MyResult, PythonTask, PythonTaskContext};
+use RustPyNet::python_pool::pool::PythonTaskContext;
 
 /// Computes the sum of two hardcoded integers.
 ///
@@ -107,22 +105,426 @@ fn compute_invalid_operation(
     Ok(PythonTaskResult::Int(0)) // This line will never be reached
 }
 
+/// Re-raises a failed parse as a `KeyError`, to demonstrate chained Python exceptions.
+#[run_with_py]
+fn lookup_with_chained_error(
+    context: PythonTaskContext,
+) -> Result<PythonTaskResult, PythonTaskError> {
+    py.run(
+        r#"
+def parse(value):
+    return int(value)
+
+try:
+    parse("not a number")
+except ValueError as err:
+    raise KeyError("missing") from err
+"#,
+        None,
+        None,
+    )?;
+    Ok(PythonTaskResult::None)
+}
+
+/// Rejects negative inputs with a Rust error, mixing it with Python errors propagated by `?`.
+#[run_with_py]
+fn checked_square_root(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    let value = match context {
+        PythonTaskContext::Float(value) => *value,
+        _ => return Err(PythonTaskError::UnsupportedValueType),
+    };
+    let root: f64 = py
+        .import("math")?
+        .getattr("sqrt")?
+        .call1((value,))?
+        .extract()?;
+    Ok(PythonTaskResult::Float(root))
+}
+
+/// Computes the sum of two hardcoded integers without blocking the caller.
+///
+/// # Returns
+///
+/// Returns a future resolving to the result of the computation wrapped in a `PythonTaskResult`.
+#[run_with_py(async)]
+fn compute_sum_async(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    let sum: i32 = py.eval("1 + 2", None, None)?.extract()?;
+    Ok(PythonTaskResult::Int(sum))
+}
+
+/// Divides by zero in Python without blocking the caller, to demonstrate async error handling.
+#[run_with_py(async)]
+fn compute_invalid_operation_async(
+    context: PythonTaskContext,
+) -> Result<PythonTaskResult, PythonTaskError> {
+    let _: i32 = py.eval("1 / 0", None, None)?.extract()?;
+    Ok(PythonTaskResult::Int(0))
+}
+
+/// Sleeps for a short while inside Python, keeping the worker busy.
+#[run_with_py]
+fn sleep_briefly(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    py.run("import time; time.sleep(0.3)", None, None)?;
+    Ok(PythonTaskResult::None)
+}
+
+/// Sleeps for longer than its 50 ms timeout, to demonstrate timeouts.
+#[run_with_py(timeout_ms = 50)]
+fn sleep_past_timeout(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    py.run("import time; time.sleep(0.3)", None, None)?;
+    Ok(PythonTaskResult::None)
+}
+
+/// Never returns on its own, to demonstrate how runaway tasks are interrupted.
+#[run_with_py]
+fn spin_forever(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    py.run("while True: pass", None, None)?;
+    Ok(PythonTaskResult::None)
+}
+
+/// Runs until its caller cancels it, checking the cancellation flag from Python.
+#[run_with_py]
+fn wait_for_cancellation(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+    py.run(
+        "import rustpynet, time\nwhile not rustpynet.cancelled(): time.sleep(0.01)",
+        None,
+        None,
+    )?;
+    Ok(PythonTaskResult::Str("stopped".to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pyo3::types::IntoPyDict;
+    use lazy_static::lazy_static;
+    use pyo3::Python;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use RustPyNet::python_pool::builder::PythonPoolBuilder;
+    use RustPyNet::python_pool::pool::{
+        MyResult, OverflowPolicy, PythonTask, PythonTaskQueue, TaskOptions, TaskPriority,
+    };
+    use RustPyNet::python_pool::task_handle::{CancelOutcome, TaskStatus};
+
+    static EXECUTED_MARKERS: AtomicUsize = AtomicUsize::new(0);
+
+    // Counts how many times it was executed, to observe skipped tasks.
+    #[run_with_py]
+    fn mark_executed(context: PythonTaskContext) -> Result<PythonTaskResult, PythonTaskError> {
+        EXECUTED_MARKERS.fetch_add(1, Ordering::SeqCst);
+        Ok(PythonTaskResult::None)
+    }
+
+    #[run_with_py(priority = high)]
+    fn compute_sum_urgently(
+        context: PythonTaskContext,
+    ) -> Result<PythonTaskResult, PythonTaskError> {
+        let result: i32 = py.eval("1 + 2", None, None)?.extract()?;
+        Ok(PythonTaskResult::Int(result))
+    }
+
+    #[run_with_py(pool = "analytics")]
+    fn compute_sum_for_analytics(
+        context: PythonTaskContext,
+    ) -> Result<PythonTaskResult, PythonTaskError> {
+        let result: i32 = py.eval("1 + 2", None, None)?.extract()?;
+        Ok(PythonTaskResult::Int(result))
+    }
+
+    // No pool is ever started for this queue, so calls can only time out.
+    #[run_with_py(async, pool = "unprocessed")]
+    fn compute_sum_unprocessed_async(
+        context: PythonTaskContext,
+    ) -> Result<PythonTaskResult, PythonTaskError> {
+        let result: i32 = py.eval("1 + 2", None, None)?.extract()?;
+        Ok(PythonTaskResult::Int(result))
+    }
+
+    // Relies on the interpreter setup of the pool started by `test_pool_from_toml`.
+    #[run_with_py(pool = "configured")]
+    fn read_configured_answer(
+        context: PythonTaskContext,
+    ) -> Result<PythonTaskResult, PythonTaskError> {
+        let answer: i32 = py
+            .eval(
+                "rustpynet_builder_module.ANSWER + int(__import__('os').environ['RUSTPYNET_TEST_OFFSET'])",
+                Some([("rustpynet_builder_module", py.import("rustpynet_builder_module")?)].into_py_dict(py)),
+                None,
+            )?
+            .extract()?;
+        Ok(PythonTaskResult::Int(answer))
+    }
+
+    #[run_with_py]
+    fn count_word_lengths(context: PythonTaskContext) -> Result<Vec<usize>, PythonTaskError> {
+        Ok(py
+            .eval(
+                "[len(word) for word in 'the quick fox'.split()]",
+                None,
+                None,
+            )?
+            .extract()?)
+    }
+
+    #[run_with_py]
+    fn repeat_greeting(context: PythonTaskContext) -> Result<String, pyo3::PyErr> {
+        py.eval("'hi ' * 2", None, None)?.extract()
+    }
+
+    #[run_with_py(timeout_ms = 50)]
+    fn sleep_into_py_err(context: PythonTaskContext) -> Result<(), pyo3::PyErr> {
+        py.run("import time; time.sleep(0.3)", None, None)
+    }
+
+    #[run_with_py]
+    fn weighted_score(user_id: i64, weights: Vec<f64>) -> Result<f64, pyo3::PyErr> {
+        let locals = [
+            ("user_id", user_id.to_object(py)),
+            ("weights", weights.to_object(py)),
+        ]
+        .into_py_dict(py);
+        py.eval("user_id * sum(weights)", None, Some(locals))?
+            .extract()
+    }
+
+    #[run_with_py]
+    fn shout(text: &str, mut times: usize) -> Result<String, PythonTaskError> {
+        let mut shouted = String::new();
+        while times > 0 {
+            let upper: String = py
+                .eval(&format!("{:?}.upper()", text), None, None)?
+                .extract()?;
+            shouted.push_str(&upper);
+            times -= 1;
+        }
+        Ok(shouted)
+    }
+
+    #[run_with_py]
+    fn count_words_with_py(py: Python) -> Result<usize, PythonTaskError> {
+        Ok(py
+            .eval("len('the quick fox'.split())", None, None)?
+            .extract()?)
+    }
+
+    // The `Python` parameter can have any name.
+    #[run_with_py]
+    fn greet_with_gil(gil: Python, name: &str) -> Result<String, pyo3::PyErr> {
+        gil.eval(&format!("'hi ' + {:?}", name), None, None)?
+            .extract()
+    }
+
+    #[run_with_py(timeout_ms = 50)]
+    fn sleep_with_py(py: Python) -> Result<(), pyo3::PyErr> {
+        py.run("import time; time.sleep(0.3)", None, None)
+    }
+
+    // Has no implicit `py`, so a local of that name is the function's own.
+    #[run_with_py(explicit_py)]
+    fn is_script(gil: Python, path: String) -> Result<bool, pyo3::PyErr> {
+        let py = path.ends_with(".py");
+        Ok(py
+            && gil
+                .import("os.path")?
+                .call_method1("isfile", (path,))?
+                .extract()?)
+    }
+
+    // Only imports the macro, to check that the expansion doesn't depend on the imports of the
+    // file, and names its parameters like the locals of the expansion.
+    mod bare_imports {
+        use RustPyNet::run_with_py;
+
+        #[run_with_py]
+        fn add_offsets(py: pyo3::Python, options: i64, timeout: i64) -> Result<i64, pyo3::PyErr> {
+            let base: i64 = py.eval("1", None, None)?.extract()?;
+            Ok(base + options + timeout)
+        }
+
+        #[test]
+        fn test_expansion_only_needs_the_macro() {
+            super::setup();
+
+            assert_eq!(add_offsets(2, 3).unwrap(), 6);
+        }
+    }
+
+    // Exports its functions, with the attributes and generics they are declared with.
+    mod exported {
+        use pyo3::{PyErr, Python, ToPyObject};
+        use RustPyNet::run_with_py;
+
+        /// Sends a value through Python and back.
+        #[run_with_py]
+        #[must_use]
+        pub fn roundtrip<T>(py: Python, value: T) -> Result<T, PyErr>
+        where
+            T: ToPyObject + for<'p> pyo3::FromPyObject<'p>,
+        {
+            value.to_object(py).extract(py)
+        }
+
+        // Fails to compile unless the `cfg` is carried over to every generated item.
+        #[run_with_py]
+        #[cfg(any())]
+        pub fn never_compiled(py: Python) -> Result<(), PyErr> {
+            compile_error!("the `cfg` attribute was dropped")
+        }
+    }
+
+    // Domain logic kept on a struct, with methods running on the pool.
+    #[derive(Clone)]
+    struct Analyzer {
+        threshold: usize,
+        weights: Vec<i64>,
+    }
+
+    impl Analyzer {
+        #[run_with_py]
+        fn classify(&self, py: Python, text: &str) -> Result<String, pyo3::PyErr> {
+            let length: usize = py
+                .eval(&format!("len({:?})", text), None, None)?
+                .extract()?;
+            Ok(if length > self.threshold {
+                "long"
+            } else {
+                "short"
+            }
+            .to_string())
+        }
+
+        #[run_with_py(async)]
+        fn classify_async(&self, py: Python, text: String) -> Result<String, pyo3::PyErr> {
+            let length: usize = py
+                .eval(&format!("len({:?})", text), None, None)?
+                .extract()?;
+            Ok(if length > self.threshold {
+                "long"
+            } else {
+                "short"
+            }
+            .to_string())
+        }
+
+        #[run_with_py]
+        fn into_total(self) -> Result<i64, PythonTaskError> {
+            let locals = [("weights", self.weights.to_object(py))].into_py_dict(py);
+            Ok(py.eval("sum(weights)", None, Some(locals))?.extract()?)
+        }
+    }
+
+    // A result type of the application, only known to Rust.
+    #[derive(Debug, PartialEq)]
+    struct Summary {
+        total: i64,
+        count: usize,
+    }
+
+    // An error type of the application, covering both Python and pool failures.
+    #[derive(Debug)]
+    enum SummaryError {
+        Python(String),
+        Pool(PythonTaskError),
+    }
+
+    impl std::fmt::Display for SummaryError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                SummaryError::Python(message) => write!(f, "python failed: {}", message),
+                SummaryError::Pool(err) => write!(f, "pool failed: {}", err),
+            }
+        }
+    }
+
+    impl From<pyo3::PyErr> for SummaryError {
+        fn from(err: pyo3::PyErr) -> Self {
+            SummaryError::Python(err.to_string())
+        }
+    }
+
+    impl From<PythonTaskError> for SummaryError {
+        fn from(err: PythonTaskError) -> Self {
+            SummaryError::Pool(err)
+        }
+    }
+
+    #[run_with_py]
+    fn summarize(context: PythonTaskContext) -> Result<Summary, SummaryError> {
+        let values: Vec<i64> = match context {
+            PythonTaskContext::List(_) => py.eval("[4, 5, 6]", None, None)?.extract()?,
+            _ => py.eval("[1, 2, undefined]", None, None)?.extract()?,
+        };
+        Ok(Summary {
+            total: values.iter().sum(),
+            count: values.len(),
+        })
+    }
+
+    #[run_with_py]
+    fn word_bounds(py: Python) -> Result<(usize, usize), pyo3::PyErr> {
+        py.eval("(len('a'), len('abc'))", None, None)?.extract()
+    }
+
+    #[run_with_py]
+    fn words_by_length(
+        py: Python,
+    ) -> Result<std::collections::BTreeMap<usize, String>, pyo3::PyErr> {
+        py.eval("{len(w): w for w in ['a', 'abc']}", None, None)?
+            .extract()
+    }
+
+    // Returns its value, like tasks written by hand rather than generated.
+    struct ConstantTask(i32);
+
+    impl PythonTask for ConstantTask {
+        fn execute(&self, _py: Python) -> MyResult<PythonTaskResult> {
+            Ok(PythonTaskResult::Int(self.0))
+        }
+    }
+
+    // Records its label when executed, to observe the order tasks run in.
+    struct RecordOrderTask {
+        label: &'static str,
+        order: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl PythonTask for RecordOrderTask {
+        fn execute(&self, _py: Python) -> MyResult<PythonTaskResult> {
+            self.order.lock().unwrap().push(self.label);
+            Ok(PythonTaskResult::None)
+        }
+    }
+
+    lazy_static! {
+        // A single pool shared by every test, processing the global queue.
+        static ref POOL: PythonPool = PythonPool::start().expect("Failed to start the Python pool");
+    }
 
     // This function is run before tests and it sets up necessary environment
     fn setup() {
-        // Initialize the Python interpreter
-        pyo3::prepare_freethreaded_python();
+        lazy_static::initialize(&POOL);
+    }
 
-        // Start processing tasks in a separate thread
-        std::thread::spawn(move || {
-            start_processing_host_python_tasks();
-        });
+    struct ThreadWaker(std::thread::Thread);
 
-        std::thread::sleep(std::time::Duration::from_secs(2)); // for example, wait for 5 seconds
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // A minimal executor, to show the futures don't depend on any particular runtime.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let waker = std::sync::Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = std::task::Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                std::task::Poll::Ready(output) => return output,
+                std::task::Poll::Pending => std::thread::park(),
+            }
+        }
     }
 
     #[test]
@@ -177,22 +579,1074 @@ mod tests {
         let context = PythonTaskContext::None;
         let result = compute_invalid_operation(&context);
         match result {
-            Err(PythonTaskError::PythonError(_)) => assert!(true),
+            Err(PythonTaskError::PythonError(exception)) => {
+                assert_eq!(exception.qualified_name(), "builtins.ZeroDivisionError");
+                assert_eq!(exception.message, "division by zero");
+            }
             _ => panic!("Test failed! Should have raised a Python error."),
         }
     }
-}
 
-fn main() {
-    // Initialize the Python interpreter
-    pyo3::prepare_freethreaded_python();
+    #[test]
+    fn test_python_exception_details() {
+        setup();
+
+        let exception = match lookup_with_chained_error(&PythonTaskContext::None) {
+            Err(PythonTaskError::PythonError(exception)) => exception,
+            other => panic!("Expected a Python exception, got {:?}", other),
+        };
+        assert!(exception.is("KeyError"));
+        assert_eq!(exception.args, vec!["'missing'".to_string()]);
+        assert_eq!(exception.traceback.len(), 1);
+
+        let cause = exception
+            .cause
+            .as_ref()
+            .expect("Expected the ValueError cause");
+        assert!(cause.is("builtins.ValueError"));
+        assert_eq!(
+            cause
+                .traceback
+                .iter()
+                .map(|frame| frame.function.as_str())
+                .collect::<Vec<_>>(),
+            vec!["<module>", "parse"]
+        );
+        assert_eq!(cause.traceback[1].line, 3);
+        // `raise ... from` suppresses the implicit context.
+        assert!(exception.context.is_none());
+
+        let formatted = exception.to_string();
+        assert!(formatted.contains("The above exception was the direct cause"));
+        assert!(formatted.ends_with("KeyError: 'missing'"));
+    }
+
+    #[test]
+    fn test_task_errors_are_std_errors() {
+        setup();
+
+        fn run() -> Result<PythonTaskResult, Box<dyn std::error::Error>> {
+            Ok(compute_invalid_operation(&PythonTaskContext::None)?)
+        }
+
+        let err = run().unwrap_err();
+        assert_eq!(err.to_string(), "ZeroDivisionError: division by zero");
+        let task_error = err.downcast_ref::<PythonTaskError>().unwrap();
+        let exception = std::error::Error::source(task_error).expect("Expected the exception");
+        assert!(exception
+            .to_string()
+            .starts_with("Traceback (most recent call last):"));
+
+        assert_eq!(
+            PythonTaskError::QueueFull.to_string(),
+            "the Python task queue is full"
+        );
+    }
+
+    #[test]
+    fn test_errors_flow_between_rust_and_python() {
+        setup();
+
+        match checked_square_root(&PythonTaskContext::Str("four".to_string())) {
+            Err(PythonTaskError::UnsupportedValueType) => {}
+            other => panic!("Expected the Rust error, got {:?}", other),
+        }
+        let err = match checked_square_root(&PythonTaskContext::Float(-1.0)) {
+            Err(err @ PythonTaskError::PythonError(_)) => err,
+            other => panic!("Expected the Python error, got {:?}", other),
+        };
+
+        Python::with_gil(|py| {
+            // The original exception is raised again in Python.
+            let py_err = pyo3::PyErr::from(err);
+            assert!(py_err.is_instance::<pyo3::exceptions::PyValueError>(py));
+            assert_eq!(py_err.pvalue(py).to_string(), "math domain error");
 
-    // Start processing tasks in a separate thread
-    std::thread::spawn(move || {
-        start_processing_host_python_tasks();
-    });
+            let py_err = pyo3::PyErr::from(PythonTaskError::Timeout);
+            assert!(py_err.is_instance::<pyo3::exceptions::PyTimeoutError>(py));
+        });
+    }
+
+    #[test]
+    fn test_functions_return_their_declared_type() {
+        setup();
 
-    std::thread::sleep(std::time::Duration::from_secs(2)); // Whait pool initialize!
+        assert_eq!(
+            count_word_lengths(&PythonTaskContext::None).unwrap(),
+            vec![3, 5, 3]
+        );
+        assert_eq!(repeat_greeting(&PythonTaskContext::None).unwrap(), "hi hi ");
+        assert_eq!(word_bounds().unwrap(), (1, 3));
+        assert_eq!(
+            words_by_length().unwrap(),
+            [(1, "a".to_string()), (3, "abc".to_string())].into()
+        );
+        assert_eq!(
+            summarize(&PythonTaskContext::List(Vec::new())).unwrap(),
+            Summary {
+                total: 15,
+                count: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_functions_take_an_explicit_py() {
+        setup();
+
+        assert_eq!(count_words_with_py().unwrap(), 3);
+        assert_eq!(greet_with_gil("bob").unwrap(), "hi bob");
+        let script = std::env::temp_dir().join("rustpynet_is_script.py");
+        std::fs::write(&script, "").unwrap();
+        assert!(is_script(script.to_string_lossy().into_owned()).unwrap());
+        assert!(!is_script(script.with_extension("txt").to_string_lossy().into_owned()).unwrap());
+        std::fs::remove_file(&script).unwrap();
+        let err = sleep_with_py().unwrap_err();
+        Python::with_gil(|py| assert!(err.is_instance::<pyo3::exceptions::PyTimeoutError>(py)));
+
+        // The task only holds the other parameters.
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let handle = queue.enqueue(Box::new(GreetWithGilTask::new("ann".to_string())));
+        match handle.wait() {
+            Ok(PythonTaskResult::Str(greeting)) => assert_eq!(greeting, "hi ann"),
+            other => panic!("Expected the greeting, got {:?}", other),
+        }
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_functions_take_typed_parameters() {
+        setup();
+
+        assert_eq!(weighted_score(2, vec![0.5, 1.5]).unwrap(), 4.0);
+        assert_eq!(shout("hey", 2).unwrap(), "HEYHEY");
+        assert_eq!(
+            shout_with_timeout("ho", 1, Duration::from_secs(5)).unwrap(),
+            "HO"
+        );
+
+        // Borrowed parameters are held as owned values by the task.
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let handle = queue.enqueue(Box::new(ShoutTask::new("hi".to_string(), 3)));
+        match handle.wait() {
+            Ok(PythonTaskResult::Str(value)) => assert_eq!(value, "HIHIHI"),
+            other => panic!("Expected the shouted text, got {:?}", other),
+        }
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_functions_keep_their_visibility_and_generics() {
+        setup();
+
+        assert_eq!(exported::roundtrip(7i64).unwrap(), 7);
+        assert_eq!(
+            exported::roundtrip_with_timeout("seven".to_string(), Duration::from_secs(5)).unwrap(),
+            "seven"
+        );
+
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let handle = queue.enqueue(Box::new(exported::RoundtripTask::<f64>::new(0.5)));
+        match handle.wait() {
+            Ok(PythonTaskResult::Float(value)) => assert_eq!(value, 0.5),
+            other => panic!("Expected the value, got {:?}", other),
+        }
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_methods_run_on_the_pool() {
+        setup();
+
+        let analyzer = Analyzer {
+            threshold: 4,
+            weights: vec![1, 2, 3],
+        };
+        assert_eq!(analyzer.classify("hello").unwrap(), "long");
+        assert_eq!(
+            analyzer
+                .classify_with_timeout("hi", Duration::from_secs(5))
+                .unwrap(),
+            "short"
+        );
+        assert_eq!(
+            block_on(analyzer.classify_async("hello".to_string())).unwrap(),
+            "long"
+        );
+
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+        assert_eq!(
+            analyzer
+                .classify_on(&queue, "hey", TaskOptions::new())
+                .unwrap(),
+            "short"
+        );
+        pool.shutdown(ShutdownMode::Drain);
+
+        // A method taking `self` moves the receiver into the task.
+        assert_eq!(analyzer.into_total().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_failures_use_the_declared_error_type() {
+        setup();
+
+        match summarize(&PythonTaskContext::None) {
+            Err(SummaryError::Python(message)) => assert!(message.contains("NameError")),
+            other => panic!("Expected the Python error, got {:?}", other),
+        }
+        // Failures of the pool go through `From<PythonTaskError>`.
+        let err = sleep_into_py_err(&PythonTaskContext::None).unwrap_err();
+        Python::with_gil(|py| assert!(err.is_instance::<pyo3::exceptions::PyTimeoutError>(py)));
+    }
+
+    #[test]
+    fn test_enqueued_function_reports_its_value() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let lengths = queue.enqueue(Box::new(CountWordLengthsTask::new(PythonTaskContext::None)));
+        let summary = queue.enqueue(Box::new(SummarizeTask::new(PythonTaskContext::List(
+            Vec::new(),
+        ))));
+        let failed = queue.enqueue(Box::new(SummarizeTask::new(PythonTaskContext::None)));
+        let bounds = queue.enqueue(Box::new(WordBoundsTask::new()));
+
+        match lengths.wait() {
+            Ok(PythonTaskResult::List(values)) => assert_eq!(values.len(), 3),
+            other => panic!("Expected the lengths, got {:?}", other),
+        }
+        // Values without a `PythonTaskResult` representation only report the completion.
+        match summary.wait() {
+            Ok(PythonTaskResult::None) => {}
+            other => panic!("Expected a completion, got {:?}", other),
+        }
+        match bounds.wait() {
+            Ok(PythonTaskResult::None) => {}
+            other => panic!("Expected a completion, got {:?}", other),
+        }
+        match failed.wait() {
+            Err(PythonTaskError::OtherError(message)) => {
+                assert!(message.starts_with("python failed: NameError"))
+            }
+            other => panic!("Expected the error message, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_compute_sum_async() {
+        setup();
+
+        let context = PythonTaskContext::None;
+        let result = block_on(compute_sum_async(&context));
+        match result {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            _ => panic!("Test failed!"),
+        }
+    }
+
+    #[test]
+    fn test_python_error_async() {
+        setup();
+
+        let context = PythonTaskContext::None;
+        let result = block_on(compute_invalid_operation_async(&context));
+        match result {
+            Err(PythonTaskError::PythonError(_)) => {}
+            _ => panic!("Test failed! Should have raised a Python error."),
+        }
+    }
+
+    #[test]
+    fn test_call_with_timeout() {
+        setup();
+
+        let context = PythonTaskContext::None;
+        match compute_sum_with_timeout(&context, Duration::from_secs(5)) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            _ => panic!("Test failed!"),
+        }
+        match sleep_briefly_with_timeout(&context, Duration::from_millis(50)) {
+            Err(PythonTaskError::Timeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_async_call_with_timeout() {
+        setup();
+
+        let context = PythonTaskContext::None;
+        match block_on(compute_sum_async_with_timeout(
+            &context,
+            Duration::from_secs(5),
+        )) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected a result, got {:?}", other),
+        }
+
+        let unprocessed =
+            compute_sum_unprocessed_async_with_timeout(&context, Duration::from_millis(50));
+        match block_on(unprocessed) {
+            Err(PythonTaskError::Timeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+        // The timed out task was cancelled, which removed it from the queue.
+        assert_eq!(PythonTaskQueue::named("unprocessed").len(), 0);
+    }
+
+    #[test]
+    fn test_timeout_attribute() {
+        setup();
+
+        match sleep_past_timeout(&PythonTaskContext::None) {
+            Err(PythonTaskError::Timeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_timed_out_task_is_skipped() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let busy = queue.enqueue(Box::new(SleepBrieflyTask::new(PythonTaskContext::None)));
+        let timeout = Duration::from_millis(50);
+        let rx = queue.enqueue_with_timeout(
+            Box::new(MarkExecutedTask::new(PythonTaskContext::None)),
+            timeout,
+        );
+
+        match PythonTaskQueue::wait_for_result_timeout(rx, timeout) {
+            Err(PythonTaskError::Timeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+
+        assert!(PythonTaskQueue::wait_for_result(busy).is_ok());
+        pool.shutdown(ShutdownMode::Drain);
+        assert_eq!(EXECUTED_MARKERS.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_runaway_task_is_interrupted() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let runaway = queue.enqueue_with_timeout(
+            Box::new(SpinForeverTask::new(PythonTaskContext::None)),
+            Duration::from_millis(100),
+        );
+        let next = queue.enqueue(Box::new(ComputeSumTask::new(PythonTaskContext::None)));
+
+        match PythonTaskQueue::wait_for_result(runaway) {
+            Err(PythonTaskError::Interrupted) => {}
+            other => panic!("Expected the task to be interrupted, got {:?}", other),
+        }
+        match PythonTaskQueue::wait_for_result(next) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the next task to run, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_task_handle_reports_status_and_result() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let busy = queue.enqueue(Box::new(SleepBrieflyTask::new(PythonTaskContext::None)));
+        let handle = queue.enqueue(Box::new(ConstantTask(3)));
+
+        assert_eq!(handle.status(), TaskStatus::Queued);
+        assert!(handle.try_result().is_none());
+        match handle.wait_timeout(Duration::from_millis(10)) {
+            Err(PythonTaskError::Timeout) => {}
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+
+        // A timed wait leaves the task queued, so waiting longer still gets the result.
+        match handle.wait_timeout(Duration::from_secs(5)) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected a result, got {:?}", other),
+        }
+        assert_eq!(handle.status(), TaskStatus::Done);
+        assert_eq!(busy.status(), TaskStatus::Done);
+        assert_eq!(handle.cancel(), CancelOutcome::TooLate);
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_cancelled_queued_task_does_not_run() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let busy = queue.enqueue(Box::new(SleepBrieflyTask::new(PythonTaskContext::None)));
+        let handle = queue.enqueue(Box::new(MarkExecutedTask::new(PythonTaskContext::None)));
+
+        assert_eq!(handle.cancel(), CancelOutcome::Removed);
+        assert_eq!(handle.status(), TaskStatus::Cancelled);
+        match handle.wait() {
+            Err(PythonTaskError::Cancelled) => {}
+            other => panic!("Expected the task to be cancelled, got {:?}", other),
+        }
+
+        assert!(busy.wait().is_ok());
+        assert_eq!(pool.shutdown(ShutdownMode::Drain), 0);
+        assert_eq!(EXECUTED_MARKERS.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_running_task_sees_cancellation_from_python() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let handle = queue.enqueue(Box::new(WaitForCancellationTask::new(
+            PythonTaskContext::None,
+        )));
+        while handle.status() != TaskStatus::Running {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(handle.cancel(), CancelOutcome::Flagged);
+        match handle.wait() {
+            Ok(PythonTaskResult::Str(value)) => assert_eq!(value, "stopped"),
+            other => panic!("Expected the task to stop on its own, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_closures_run_on_the_pool() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let words = vec!["alpha".to_string(), "be".to_string()];
+        let handle = pool.submit(move |py| {
+            let locals = [("words", words)].into_py_dict(py);
+            py.eval("[len(word) for word in words]", None, Some(locals))?
+                .extract::<Vec<usize>>()
+        });
+        assert_eq!(handle.wait().unwrap(), vec![5, 2]);
+
+        let version: String = pool.run(|py| Ok(py.version().to_string())).unwrap();
+        assert!(!version.is_empty());
+
+        match pool.run(|py| py.eval("1 / 0", None, None)?.extract::<i32>()) {
+            Err(PythonTaskError::PythonError(exception)) => {
+                assert!(exception.is("ZeroDivisionError"))
+            }
+            other => panic!("Expected the Python exception, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_submitted_closure_can_be_cancelled() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let busy = queue.enqueue(Box::new(SleepBrieflyTask::new(PythonTaskContext::None)));
+        let ran = Arc::new(AtomicUsize::new(0));
+        let counter = ran.clone();
+        let handle = queue.submit_with_options(
+            move |_py| Ok(counter.fetch_add(1, Ordering::SeqCst)),
+            TaskOptions::new().priority(TaskPriority::Low),
+        );
+
+        assert_eq!(handle.status(), TaskStatus::Queued);
+        assert_eq!(handle.cancel(), CancelOutcome::Removed);
+        match handle.wait() {
+            Err(PythonTaskError::Cancelled) => {}
+            other => panic!("Expected the task to be cancelled, got {:?}", other),
+        }
+
+        assert!(busy.wait().is_ok());
+        pool.shutdown(ShutdownMode::Drain);
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_panicking_task_does_not_stop_the_worker() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        match pool.run(|py| -> PyResult<i32> {
+            let value: i32 = py.eval("1 + 2", None, None)?.extract()?;
+            panic!("unexpected value {}", value)
+        }) {
+            Err(PythonTaskError::Panicked(message)) => assert_eq!(message, "unexpected value 3"),
+            other => panic!("Expected the panic to be reported, got {:?}", other),
+        }
+
+        // The worker survived and keeps executing tasks.
+        match queue.enqueue(Box::new(ConstantTask(7))).wait() {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 7),
+            other => panic!("Expected a result, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    // Panics again when the panic it was raised with is dropped, which escapes the protection
+    // of the worker against panicking tasks and takes the worker thread down.
+    struct WorkerKiller;
+
+    impl Drop for WorkerKiller {
+        fn drop(&mut self) {
+            panic!("the worker goes down with its task");
+        }
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(std::time::Instant::now() < deadline, "Timed out waiting");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_failed_worker_is_restarted() {
+        let pool = PythonPoolBuilder::new()
+            .name("supervised")
+            .restart_backoff(Some(Duration::from_millis(10)))
+            .start()
+            .unwrap();
+        assert!(pool.is_healthy());
+        assert_eq!(pool.restarts(), 0);
+
+        let failed = pool.submit(|_py| -> PyResult<()> { std::panic::panic_any(WorkerKiller) });
+        let next = pool.queue().enqueue(Box::new(ConstantTask(5)));
+
+        match failed.wait() {
+            Err(PythonTaskError::WorkerFailed) => {}
+            other => panic!("Expected the worker failure, got {:?}", other),
+        }
+        // The restarted worker picks up the tasks still queued.
+        match next.wait() {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 5),
+            other => panic!(
+                "Expected a result from the restarted worker, got {:?}",
+                other
+            ),
+        }
+        wait_until(|| pool.restarts() == 1);
+        assert!(pool.is_healthy());
+
+        assert_eq!(pool.shutdown(ShutdownMode::Drain), 0);
+    }
+
+    #[test]
+    fn test_failed_worker_closes_the_queue_without_restarts() {
+        let pool = PythonPoolBuilder::new()
+            .name("unsupervised")
+            .restart_backoff(None)
+            .start()
+            .unwrap();
+
+        let failed = pool.submit(|_py| -> PyResult<()> { std::panic::panic_any(WorkerKiller) });
+        assert!(matches!(failed.wait(), Err(PythonTaskError::WorkerFailed)));
+        wait_until(|| !pool.is_healthy());
+
+        match pool.queue().enqueue(Box::new(ConstantTask(5))).wait() {
+            Err(PythonTaskError::PoolShutdown) => {}
+            other => panic!("Expected the task to be rejected, got {:?}", other),
+        }
+        assert_eq!(pool.restarts(), 0);
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    // Panics when cloned, which a handle does while its state is locked when it is polled.
+    fn panicking_waker() -> std::task::Waker {
+        use std::task::{RawWaker, RawWakerVTable, Waker};
+
+        fn clone(_: *const ()) -> RawWaker {
+            panic!("the waker can't be cloned");
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn test_poisoned_lock_does_not_block_callers() {
+        use std::future::Future;
+
+        let queue = PythonTaskQueue::new();
+        let mut handle = queue.enqueue(Box::new(ConstantTask(4)));
+
+        let waker = panicking_waker();
+        let polled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut cx = std::task::Context::from_waker(&waker);
+            let _ = std::pin::Pin::new(&mut handle).poll(&mut cx);
+        }));
+        assert!(polled.is_err());
+
+        // The lock of the handle is poisoned, which neither the caller nor the worker mind.
+        assert_eq!(handle.status(), TaskStatus::Queued);
+        let pool = PythonPool::start_on(&queue).unwrap();
+        match handle.wait() {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 4),
+            other => panic!("Expected a result, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    fn compute_sum_task() -> Box<ComputeSumTask> {
+        Box::new(ComputeSumTask::new(PythonTaskContext::None))
+    }
+
+    #[test]
+    fn test_generated_task_is_named_after_the_function() {
+        assert_eq!(compute_sum_task().name(), "compute_sum");
+    }
+
+    #[test]
+    fn test_full_queue_fails_fast() {
+        let queue = PythonTaskQueue::with_capacity(2, OverflowPolicy::FailFast);
+
+        let first = queue.enqueue(compute_sum_task());
+        let second = queue.enqueue(compute_sum_task());
+        let rejected = queue.enqueue(compute_sum_task());
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.capacity(), Some(2));
+        match rejected.try_result() {
+            Some(Err(PythonTaskError::QueueFull)) => {}
+            other => panic!("Expected the queue to be full, got {:?}", other),
+        }
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        assert!(first.wait().is_ok());
+        assert!(second.wait().is_ok());
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_full_queue_drops_oldest() {
+        let queue = PythonTaskQueue::with_capacity(2, OverflowPolicy::DropOldest);
+
+        let oldest = queue.enqueue(compute_sum_task());
+        let second = queue.enqueue(compute_sum_task());
+        let newest = queue.enqueue(compute_sum_task());
+
+        assert_eq!(queue.len(), 2);
+        match oldest.try_result() {
+            Some(Err(PythonTaskError::Evicted)) => {}
+            other => panic!("Expected the oldest task to be evicted, got {:?}", other),
+        }
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        assert!(second.wait().is_ok());
+        assert!(newest.wait().is_ok());
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_full_queue_blocks_producer() {
+        let queue = PythonTaskQueue::with_capacity(1, OverflowPolicy::Block);
+        let first = queue.enqueue(compute_sum_task());
+
+        let producer_queue = queue.clone();
+        let producer = std::thread::spawn(move || producer_queue.enqueue(compute_sum_task()));
+
+        wait_until(|| queue.blocked_producers() == 1);
+        assert!(!producer.is_finished());
+        assert_eq!(queue.len(), 1);
+
+        // The worker frees the slot the producer is waiting for.
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let second = producer.join().unwrap();
+        assert!(first.wait().is_ok());
+        assert!(second.wait().is_ok());
+        assert_eq!(pool.queue_depth(), 0);
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_full_queue_parks_async_producer() {
+        use std::future::Future;
+
+        let queue = PythonTaskQueue::with_capacity(1, OverflowPolicy::Block);
+        let first = queue.enqueue(compute_sum_task());
+
+        // Waiting for room leaves the thread polling the future free.
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = std::task::Context::from_waker(&waker);
+        let mut call = Box::pin(compute_sum_async_on(
+            &queue,
+            &PythonTaskContext::None,
+            TaskOptions::new(),
+        ));
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(queue.blocked_producers(), 1);
+        assert_eq!(queue.len(), 1);
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        match block_on(call) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the sum, got {:?}", other),
+        }
+        assert!(first.wait().is_ok());
+        assert_eq!(queue.blocked_producers(), 0);
+        pool.shutdown(ShutdownMode::Drain);
+
+        let full = PythonTaskQueue::with_capacity(0, OverflowPolicy::Block);
+        let options = TaskOptions::new().timeout(Duration::from_millis(20));
+        let timed_out = block_on(full.enqueue_async(compute_sum_task(), options));
+        assert!(matches!(timed_out.wait(), Err(PythonTaskError::Timeout)));
+
+        // Dropping a waiting future withdraws its task.
+        let mut pending = Box::pin(full.enqueue_async(compute_sum_task(), TaskOptions::new()));
+        assert!(pending.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(full.blocked_producers(), 1);
+        drop(pending);
+        assert_eq!(full.blocked_producers(), 0);
+        assert!(full.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_producers_keep_their_order() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let executed = Arc::new(Mutex::new(Vec::new()));
+
+        let producers: Vec<_> = (0..4)
+            .map(|producer| {
+                let queue = queue.clone();
+                let executed = executed.clone();
+                std::thread::spawn(move || {
+                    let handles: Vec<_> = (0..250)
+                        .map(|index| {
+                            let executed = executed.clone();
+                            queue.submit(move |_py| {
+                                executed.lock().unwrap().push((producer, index));
+                                Ok(())
+                            })
+                        })
+                        .collect();
+                    for handle in handles {
+                        handle.wait().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let executed = executed.lock().unwrap();
+        assert_eq!(executed.len(), 1000);
+        for producer in 0..4 {
+            let indices: Vec<_> = executed
+                .iter()
+                .filter(|(from, _)| *from == producer)
+                .map(|(_, index)| *index)
+                .collect();
+            assert_eq!(indices, (0..250).collect::<Vec<_>>());
+        }
+        assert_eq!(pool.queue_depth(), 0);
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_higher_priorities_run_first() {
+        let queue = PythonTaskQueue::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for (label, priority) in [
+            ("low", TaskPriority::Low),
+            ("normal", TaskPriority::Normal),
+            ("high", TaskPriority::High),
+            ("second high", TaskPriority::High),
+        ] {
+            let task = Box::new(RecordOrderTask {
+                label,
+                order: order.clone(),
+            });
+            handles.push(queue.enqueue_with_priority(task, priority));
+        }
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        for handle in handles {
+            assert!(handle.wait().is_ok());
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["high", "second high", "normal", "low"]
+        );
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_low_priority_task_ages() {
+        let queue = PythonTaskQueue::new();
+        // Without an interval to wait for, every task is aged to the highest priority at once.
+        queue.set_aging(Some(Duration::ZERO));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let low = queue.enqueue_with_options(
+            Box::new(RecordOrderTask {
+                label: "low",
+                order: order.clone(),
+            }),
+            TaskOptions::new().priority(TaskPriority::Low),
+        );
+        let high = queue.enqueue_with_priority(
+            Box::new(RecordOrderTask {
+                label: "high",
+                order: order.clone(),
+            }),
+            TaskPriority::High,
+        );
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        assert!(low.wait().is_ok());
+        assert!(high.wait().is_ok());
+        assert_eq!(*order.lock().unwrap(), vec!["low", "high"]);
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_priority_attribute() {
+        setup();
+
+        match compute_sum_urgently(&PythonTaskContext::None) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the sum, got {:?}", other),
+        }
+
+        let options = TaskOptions::new().priority(TaskPriority::Low);
+        match compute_sum_with_options(&PythonTaskContext::None, options) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the sum, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_named_pool_attribute() {
+        let pool = PythonPool::start_named("analytics").unwrap();
+
+        match compute_sum_for_analytics(&PythonTaskContext::None) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the sum, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_named_pools_are_independent() {
+        let slow_pool = PythonPool::start_named("slow").unwrap();
+        let fast_pool = PythonPool::start_named("fast").unwrap();
+
+        // Park the slow pool on a task that waits for the gate, without holding the GIL.
+        let (release, gate) = std::sync::mpsc::channel::<()>();
+        let parked = PythonTaskQueue::named("slow").submit(move |py| {
+            py.allow_threads(move || gate.recv()).unwrap();
+            Ok(())
+        });
+        wait_until(|| parked.status() == TaskStatus::Running);
+
+        // The timeout only turns a fast pool stuck behind the slow one into a failure.
+        let fast_queue = PythonTaskQueue::named("fast");
+        let options = TaskOptions::new().timeout(Duration::from_secs(5));
+        match compute_sum_on(&fast_queue, &PythonTaskContext::None, options) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+            other => panic!("Expected the sum, got {:?}", other),
+        }
+        assert_eq!(parked.status(), TaskStatus::Running);
+
+        release.send(()).unwrap();
+        parked.wait().unwrap();
+        slow_pool.shutdown(ShutdownMode::Drain);
+        fast_pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_pool_from_toml() {
+        let dir = std::env::temp_dir().join(format!("rustpynet-builder-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("rustpynet_builder_module.py"), "ANSWER = 40\n").unwrap();
+
+        let toml = format!(
+            r#"
+            name = "configured"
+            capacity = 8
+            overflow = "fail_fast"
+            logging = false
+            sys_path = [{:?}]
+            preload = ["rustpynet_builder_module"]
+
+            [env]
+            RUSTPYNET_TEST_OFFSET = "2"
+            "#,
+            dir.to_str().unwrap()
+        );
+        let builder = PythonPoolBuilder::from_toml_str(&toml).unwrap();
+        let pool = builder.start().unwrap();
+        assert_eq!(pool.queue().capacity(), Some(8));
+
+        match read_configured_answer(&PythonTaskContext::None) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 42),
+            other => panic!("Expected the configured answer, got {:?}", other),
+        }
+
+        pool.shutdown(ShutdownMode::Drain);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pool_from_env() {
+        // The variables are passed in rather than set, since other tests run concurrently.
+        let mut vars = HashMap::from([
+            ("RUSTPYNET_NAME", "from-env"),
+            ("RUSTPYNET_CAPACITY", "3"),
+            ("RUSTPYNET_MAX_BATCH", "1"),
+            (
+                "RUSTPYNET_ENV",
+                "RUSTPYNET_TEST_COLOR=blue, RUSTPYNET_TEST_SIZE=3",
+            ),
+        ]);
+        let builder = PythonPoolBuilder::new()
+            .env_overrides_with(|key| vars.get(key).map(std::ffi::OsString::from));
+        vars.insert("RUSTPYNET_ENV", "RUSTPYNET_TEST_COLOR");
+        let invalid = PythonPoolBuilder::new()
+            .env_overrides_with(|key| vars.get(key).map(std::ffi::OsString::from));
+        assert!(invalid.is_err());
+
+        let pool = builder.unwrap().start().unwrap();
+        assert_eq!(pool.queue().capacity(), Some(3));
+        let handle = pool.queue().enqueue(compute_sum_task());
+        assert!(handle.wait().is_ok());
+        let environ: (String, String) = pool
+            .run(|py| {
+                py.eval(
+                    "(__import__('os').environ['RUSTPYNET_TEST_COLOR'], __import__('os').environ['RUSTPYNET_TEST_SIZE'])",
+                    None,
+                    None,
+                )?
+                .extract()
+            })
+            .unwrap();
+        assert_eq!(environ, ("blue".to_string(), "3".to_string()));
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_invalid_pool_configuration() {
+        assert!(PythonPoolBuilder::from_toml_str("overflow = \"sometimes\"").is_err());
+        assert!(PythonPoolBuilder::from_toml_str("unknown_key = 1").is_err());
+
+        // A failed preload keeps the pool from starting, and leaves the queue free.
+        let failing = PythonPoolBuilder::new()
+            .name("failing-preload")
+            .preload("rustpynet_module_that_does_not_exist");
+        assert!(failing.start().is_err());
+        let pool = PythonPool::start_named("failing-preload").unwrap();
+
+        // A builder for a queue that is already processed leaves its settings alone.
+        let conflicting = PythonPoolBuilder::new()
+            .name("failing-preload")
+            .capacity(1)
+            .overflow(OverflowPolicy::FailFast);
+        assert!(conflicting.start().is_err());
+        assert_eq!(pool.queue().capacity(), None);
+        pool.shutdown(ShutdownMode::Drain);
+    }
+
+    #[test]
+    fn test_pool_drains_queue_on_shutdown() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        let receivers: Vec<_> = (0..3)
+            .map(|_| queue.enqueue(Box::new(ComputeSumTask::new(PythonTaskContext::None))))
+            .collect();
+
+        assert_eq!(pool.shutdown(ShutdownMode::Drain), 0);
+
+        for rx in receivers {
+            match PythonTaskQueue::wait_for_result(rx) {
+                Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 3),
+                other => panic!("Expected a drained result, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pool_rejects_queue_on_shutdown() {
+        let queue = PythonTaskQueue::new();
+        let pool = PythonPool::start_on(&queue).unwrap();
+
+        // Keep the worker busy until the shutdown stops accepting tasks, probing the queue with
+        // tasks that are cancelled as long as they get queued.
+        let watched = queue.clone();
+        let busy = queue.submit(move |_py| loop {
+            let probe = watched.enqueue(Box::new(ComputeSumTask::new(PythonTaskContext::None)));
+            if probe.try_result().is_some() {
+                return Ok(());
+            }
+            probe.cancel();
+            std::thread::sleep(Duration::from_millis(1));
+        });
+        wait_until(|| busy.status() == TaskStatus::Running);
+
+        let receivers: Vec<_> = (0..3)
+            .map(|_| queue.enqueue(Box::new(ComputeSumTask::new(PythonTaskContext::None))))
+            .collect();
+
+        assert_eq!(pool.shutdown(ShutdownMode::Reject), 3);
+
+        assert!(busy.wait().is_ok());
+        for rx in receivers {
+            match PythonTaskQueue::wait_for_result(rx) {
+                Err(PythonTaskError::PoolShutdown) => {}
+                other => panic!("Expected the task to be rejected, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pool_restarts_after_shutdown() {
+        let queue = PythonTaskQueue::new();
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        assert!(PythonPool::start_on(&queue).is_err());
+        pool.shutdown(ShutdownMode::Drain);
+
+        let rx = queue.enqueue(Box::new(ComputeSumTask::new(PythonTaskContext::None)));
+        match PythonTaskQueue::wait_for_result(rx) {
+            Err(PythonTaskError::PoolShutdown) => {}
+            other => panic!("Expected the task to be rejected, got {:?}", other),
+        }
+
+        let pool = PythonPool::start_on(&queue).unwrap();
+        let rx = queue.enqueue(Box::new(ComputeProductTask::new(PythonTaskContext::None)));
+        match PythonTaskQueue::wait_for_result(rx) {
+            Ok(PythonTaskResult::Int(value)) => assert_eq!(value, 6),
+            other => panic!("Expected a result from the restarted pool, got {:?}", other),
+        }
+        pool.shutdown(ShutdownMode::Drain);
+    }
+}
+
+fn main() {
+    // Start processing tasks in a separate thread, returns once the pool is ready
+    let pool = PythonPool::start().expect("Failed to start the Python pool");
 
     const NUM_TESTS: usize = 10;
     let (tx, rx) = std::sync::mpsc::channel();
@@ -250,6 +1704,24 @@ fn main() {
             Err(PythonTaskError::UnsupportedValueType) => println!("Error: Unsupported value type"),
 
             Err(PythonTaskError::OtherError(err)) => println!("Other error: {}", err),
+
+            Err(PythonTaskError::PoolShutdown) => println!("Error: The pool was shut down"),
+
+            Err(PythonTaskError::Timeout) => println!("Error: The task timed out"),
+
+            Err(PythonTaskError::Cancelled) => println!("Error: The task was cancelled"),
+
+            Err(PythonTaskError::QueueFull) => println!("Error: The queue is full"),
+
+            Err(PythonTaskError::Evicted) => println!("Error: The task was evicted"),
+
+            Err(PythonTaskError::Interrupted) => println!("Error: The task was interrupted"),
+
+            Err(PythonTaskError::Panicked(message)) => {
+                println!("Error: The task panicked: {}", message)
+            }
+
+            Err(PythonTaskError::WorkerFailed) => println!("Error: The worker failed"),
             // ... handle other variants of PythonTaskResult and error variants ...
         }
     }
@@ -266,4 +1738,6 @@ fn main() {
             correct_responses, NUM_TESTS
         );
     }
+
+    pool.shutdown(ShutdownMode::Drain);
 }